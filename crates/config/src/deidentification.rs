@@ -0,0 +1,564 @@
+use dicom::core::Tag;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+// Action codes of the Basic Application Level Confidentiality Profile (PS3.15 Annex E).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum ProfileAction {
+    D,
+    Z,
+    X,
+    K,
+    C,
+    U,
+}
+
+impl std::fmt::Display for ProfileAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileOption {
+    RetainUids,
+    RetainDeviceIdentity,
+    RetainInstitutionIdentity,
+    RetainPatientCharacteristics,
+    RetainLongitudinalTemporalInformationFullDates,
+    RetainLongitudinalTemporalInformationModifiedDates,
+    CleanDescriptors,
+    CleanStructuredContent,
+    CleanGraphics,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Deidentification {
+    #[serde(default)]
+    pub options: HashSet<ProfileOption>,
+}
+
+// The option column of Annex E that changes the action of an attribute.
+#[derive(Clone, Copy)]
+enum Retention {
+    Never,
+    Temporal,
+    Uid,
+    Device,
+    Institution,
+    PatientCharacteristics,
+    Descriptor,
+    StructuredContent,
+    Graphics,
+}
+
+use ProfileAction::*;
+use Retention::*;
+
+// Compound actions of the standard (X/Z, X/D, Z/D, X/Z/D, X/Z/U*) are resolved to their first
+// alternative, since the graph has no Type 1 or Type 2 attributes to satisfy.
+#[rustfmt::skip]
+const ATTRIBUTES: &[(u16, u16, ProfileAction, Retention)] = &[
+    (0x0002, 0x0003, U, Uid),                      // Media Storage SOP Instance UID
+    (0x0004, 0x1511, U, Uid),                      // Referenced SOP Instance UID in File
+    (0x0008, 0x0012, X, Temporal),                 // Instance Creation Date
+    (0x0008, 0x0013, X, Temporal),                 // Instance Creation Time
+    (0x0008, 0x0014, U, Uid),                      // Instance Creator UID
+    (0x0008, 0x0015, X, Temporal),                 // Instance Coercion DateTime
+    (0x0008, 0x0017, U, Uid),                      // Acquisition UID
+    (0x0008, 0x0018, U, Uid),                      // SOP Instance UID
+    (0x0008, 0x0019, U, Uid),                      // Pyramid UID
+    (0x0008, 0x0020, Z, Temporal),                 // Study Date
+    (0x0008, 0x0021, X, Temporal),                 // Series Date
+    (0x0008, 0x0022, X, Temporal),                 // Acquisition Date
+    (0x0008, 0x0023, Z, Temporal),                 // Content Date
+    (0x0008, 0x0024, X, Temporal),                 // Overlay Date
+    (0x0008, 0x0025, X, Temporal),                 // Curve Date
+    (0x0008, 0x002A, X, Temporal),                 // Acquisition DateTime
+    (0x0008, 0x0030, Z, Temporal),                 // Study Time
+    (0x0008, 0x0031, X, Temporal),                 // Series Time
+    (0x0008, 0x0032, X, Temporal),                 // Acquisition Time
+    (0x0008, 0x0033, Z, Temporal),                 // Content Time
+    (0x0008, 0x0034, X, Temporal),                 // Overlay Time
+    (0x0008, 0x0035, X, Temporal),                 // Curve Time
+    (0x0008, 0x0050, Z, Never),                    // Accession Number
+    (0x0008, 0x0051, X, Never),                    // Issuer of Accession Number Sequence
+    (0x0008, 0x0058, U, Uid),                      // Failed SOP Instance UID List
+    (0x0008, 0x0080, X, Institution),              // Institution Name
+    (0x0008, 0x0081, X, Institution),              // Institution Address
+    (0x0008, 0x0082, X, Institution),              // Institution Code Sequence
+    (0x0008, 0x0090, Z, Never),                    // Referring Physician's Name
+    (0x0008, 0x0092, X, Never),                    // Referring Physician's Address
+    (0x0008, 0x0094, X, Never),                    // Referring Physician's Telephone Numbers
+    (0x0008, 0x0096, X, Never),                    // Referring Physician Identification Sequence
+    (0x0008, 0x009C, Z, Never),                    // Consulting Physician's Name
+    (0x0008, 0x009D, X, Never),                    // Consulting Physician Identification Sequence
+    (0x0008, 0x0201, X, Temporal),                 // Timezone Offset From UTC
+    (0x0008, 0x1010, X, Device),                   // Station Name
+    (0x0008, 0x1030, X, Descriptor),               // Study Description
+    (0x0008, 0x103E, X, Descriptor),               // Series Description
+    (0x0008, 0x1040, X, Institution),              // Institutional Department Name
+    (0x0008, 0x1041, X, Institution),              // Institutional Department Type Code Sequence
+    (0x0008, 0x1048, X, Never),                    // Physician(s) of Record
+    (0x0008, 0x1049, X, Never),                    // Physician(s) of Record Identification Sequence
+    (0x0008, 0x1050, X, Never),                    // Performing Physicians' Name
+    (0x0008, 0x1052, X, Never),                    // Performing Physician Identification Sequence
+    (0x0008, 0x1060, X, Never),                    // Name of Physician(s) Reading Study
+    (0x0008, 0x1062, X, Never),                    // Physician(s) Reading Study Identification Sequence
+    (0x0008, 0x1070, X, Never),                    // Operators' Name
+    (0x0008, 0x1072, X, Never),                    // Operator Identification Sequence
+    (0x0008, 0x1080, X, Descriptor),               // Admitting Diagnoses Description
+    (0x0008, 0x1084, X, Descriptor),               // Admitting Diagnoses Code Sequence
+    (0x0008, 0x1110, X, Never),                    // Referenced Study Sequence
+    (0x0008, 0x1111, X, Never),                    // Referenced Performed Procedure Step Sequence
+    (0x0008, 0x1120, X, Never),                    // Referenced Patient Sequence
+    (0x0008, 0x1140, X, Never),                    // Referenced Image Sequence
+    (0x0008, 0x1155, U, Uid),                      // Referenced SOP Instance UID
+    (0x0008, 0x1195, U, Uid),                      // Transaction UID
+    (0x0008, 0x2111, X, Descriptor),               // Derivation Description
+    (0x0008, 0x3010, U, Uid),                      // Irradiation Event UID
+    (0x0008, 0x4000, X, Descriptor),               // Identifying Comments
+    (0x0010, 0x0010, Z, Never),                    // Patient's Name
+    (0x0010, 0x0020, Z, Never),                    // Patient ID
+    (0x0010, 0x0021, X, Never),                    // Issuer of Patient ID
+    (0x0010, 0x0022, X, Never),                    // Type of Patient ID
+    (0x0010, 0x0024, X, Never),                    // Issuer of Patient ID Qualifiers Sequence
+    (0x0010, 0x0030, Z, Never),                    // Patient's Birth Date
+    (0x0010, 0x0032, X, Never),                    // Patient's Birth Time
+    (0x0010, 0x0033, X, Never),                    // Patient's Birth Date in Alternative Calendar
+    (0x0010, 0x0034, X, Never),                    // Patient's Death Date in Alternative Calendar
+    (0x0010, 0x0040, Z, PatientCharacteristics),   // Patient's Sex
+    (0x0010, 0x0050, X, Never),                    // Patient's Insurance Plan Code Sequence
+    (0x0010, 0x0101, X, Never),                    // Patient's Primary Language Code Sequence
+    (0x0010, 0x0102, X, Never),                    // Patient's Primary Language Modifier Code Sequence
+    (0x0010, 0x1000, X, Never),                    // Other Patient IDs
+    (0x0010, 0x1001, X, Never),                    // Other Patient Names
+    (0x0010, 0x1002, X, Never),                    // Other Patient IDs Sequence
+    (0x0010, 0x1005, X, Never),                    // Patient's Birth Name
+    (0x0010, 0x1010, X, PatientCharacteristics),   // Patient's Age
+    (0x0010, 0x1020, X, PatientCharacteristics),   // Patient's Size
+    (0x0010, 0x1030, X, PatientCharacteristics),   // Patient's Weight
+    (0x0010, 0x1040, X, Never),                    // Patient's Address
+    (0x0010, 0x1050, X, Never),                    // Insurance Plan Identification
+    (0x0010, 0x1060, X, Never),                    // Patient's Mother's Birth Name
+    (0x0010, 0x1080, X, Never),                    // Military Rank
+    (0x0010, 0x1081, X, Never),                    // Branch of Service
+    (0x0010, 0x1090, X, Never),                    // Medical Record Locator
+    (0x0010, 0x1100, X, Never),                    // Referenced Patient Photo Sequence
+    (0x0010, 0x2000, X, Never),                    // Medical Alerts
+    (0x0010, 0x2110, X, Never),                    // Allergies
+    (0x0010, 0x2150, X, Never),                    // Country of Residence
+    (0x0010, 0x2152, X, Never),                    // Region of Residence
+    (0x0010, 0x2154, X, Never),                    // Patient's Telephone Numbers
+    (0x0010, 0x2155, X, Never),                    // Patient's Telecom Information
+    (0x0010, 0x2160, X, PatientCharacteristics),   // Ethnic Group
+    (0x0010, 0x2180, X, Never),                    // Occupation
+    (0x0010, 0x21A0, X, PatientCharacteristics),   // Smoking Status
+    (0x0010, 0x21B0, X, Descriptor),               // Additional Patient History
+    (0x0010, 0x21C0, X, PatientCharacteristics),   // Pregnancy Status
+    (0x0010, 0x21D0, X, Temporal),                 // Last Menstrual Date
+    (0x0010, 0x21F0, X, Never),                    // Patient's Religious Preference
+    (0x0010, 0x2203, X, PatientCharacteristics),   // Patient's Sex Neutered
+    (0x0010, 0x2297, X, Never),                    // Responsible Person
+    (0x0010, 0x2299, X, Never),                    // Responsible Organization
+    (0x0010, 0x4000, X, Descriptor),               // Patient Comments
+    (0x0018, 0x1000, X, Device),                   // Device Serial Number
+    (0x0018, 0x1002, U, Uid),                      // Device UID
+    (0x0018, 0x1004, X, Device),                   // Plate ID
+    (0x0018, 0x1005, X, Device),                   // Generator ID
+    (0x0018, 0x1007, X, Device),                   // Cassette ID
+    (0x0018, 0x1008, X, Device),                   // Gantry ID
+    (0x0018, 0x1009, X, Device),                   // Unique Device Identifier
+    (0x0018, 0x100A, X, Device),                   // UDI Sequence
+    (0x0018, 0x100B, U, Uid),                      // Manufacturer's Device Class UID
+    (0x0018, 0x1012, X, Temporal),                 // Date of Secondary Capture
+    (0x0018, 0x1014, X, Temporal),                 // Time of Secondary Capture
+    (0x0018, 0x1030, X, Descriptor),               // Protocol Name
+    (0x0018, 0x1072, X, Temporal),                 // Radiopharmaceutical Start Time
+    (0x0018, 0x1073, X, Temporal),                 // Radiopharmaceutical Stop Time
+    (0x0018, 0x1078, X, Temporal),                 // Radiopharmaceutical Start DateTime
+    (0x0018, 0x1079, X, Temporal),                 // Radiopharmaceutical Stop DateTime
+    (0x0018, 0x1200, X, Temporal),                 // Date of Last Calibration
+    (0x0018, 0x1201, X, Temporal),                 // Time of Last Calibration
+    (0x0018, 0x1202, X, Temporal),                 // DateTime of Last Calibration
+    (0x0018, 0x1400, X, Descriptor),               // Acquisition Device Processing Description
+    (0x0018, 0x4000, X, Descriptor),               // Acquisition Comments
+    (0x0018, 0x700A, X, Device),                   // Detector ID
+    (0x0018, 0x9074, X, Temporal),                 // Frame Acquisition DateTime
+    (0x0018, 0x9151, X, Temporal),                 // Frame Reference DateTime
+    (0x0018, 0x9185, X, Descriptor),               // Respiratory Motion Compensation Technique Description
+    (0x0018, 0x9367, X, Device),                   // X-Ray Source ID
+    (0x0018, 0x9371, X, Device),                   // X-Ray Detector ID
+    (0x0018, 0x9373, X, Descriptor),               // X-Ray Detector Label
+    (0x0018, 0x937B, X, Descriptor),               // Multi-energy Acquisition Description
+    (0x0018, 0x9424, X, Descriptor),               // Acquisition Protocol Description
+    (0x0018, 0x9516, X, Temporal),                 // Start Acquisition DateTime
+    (0x0018, 0x9517, X, Temporal),                 // End Acquisition DateTime
+    (0x0018, 0x9623, X, Temporal),                 // Functional Sync Pulse
+    (0x0018, 0x9701, X, Temporal),                 // Decay Correction DateTime
+    (0x0018, 0x9804, X, Temporal),                 // Exclusion Start DateTime
+    (0x0018, 0xA003, X, Descriptor),               // Contribution Description
+    (0x0020, 0x000D, U, Uid),                      // Study Instance UID
+    (0x0020, 0x000E, U, Uid),                      // Series Instance UID
+    (0x0020, 0x0010, Z, Never),                    // Study ID
+    (0x0020, 0x0052, U, Uid),                      // Frame of Reference UID
+    (0x0020, 0x0200, U, Uid),                      // Synchronization Frame of Reference UID
+    (0x0020, 0x3401, X, Device),                   // Modifying Device ID
+    (0x0020, 0x3406, X, Descriptor),               // Modified Image Description
+    (0x0020, 0x4000, X, Descriptor),               // Image Comments
+    (0x0020, 0x9158, X, Descriptor),               // Frame Comments
+    (0x0020, 0x9161, U, Uid),                      // Concatenation UID
+    (0x0020, 0x9164, U, Uid),                      // Dimension Organization UID
+    (0x0028, 0x1199, U, Uid),                      // Palette Color Lookup Table UID
+    (0x0028, 0x1214, U, Uid),                      // Large Palette Color Lookup Table UID
+    (0x0028, 0x4000, X, Descriptor),               // Image Presentation Comments
+    (0x0032, 0x0012, X, Never),                    // Study ID Issuer
+    (0x0032, 0x0032, X, Temporal),                 // Study Verified Date
+    (0x0032, 0x0033, X, Temporal),                 // Study Verified Time
+    (0x0032, 0x0034, X, Temporal),                 // Study Read Date
+    (0x0032, 0x0035, X, Temporal),                 // Study Read Time
+    (0x0032, 0x1000, X, Temporal),                 // Scheduled Study Start Date
+    (0x0032, 0x1001, X, Temporal),                 // Scheduled Study Start Time
+    (0x0032, 0x1010, X, Temporal),                 // Scheduled Study Stop Date
+    (0x0032, 0x1011, X, Temporal),                 // Scheduled Study Stop Time
+    (0x0032, 0x1020, X, Never),                    // Scheduled Study Location
+    (0x0032, 0x1021, X, Never),                    // Scheduled Study Location AE Title
+    (0x0032, 0x1030, X, Descriptor),               // Reason for Study
+    (0x0032, 0x1032, X, Never),                    // Requesting Physician
+    (0x0032, 0x1033, X, Never),                    // Requesting Service
+    (0x0032, 0x1040, X, Temporal),                 // Study Arrival Date
+    (0x0032, 0x1041, X, Temporal),                 // Study Arrival Time
+    (0x0032, 0x1050, X, Temporal),                 // Study Completion Date
+    (0x0032, 0x1051, X, Temporal),                 // Study Completion Time
+    (0x0032, 0x1060, X, Descriptor),               // Requested Procedure Description
+    (0x0032, 0x1070, X, Descriptor),               // Requested Contrast Agent
+    (0x0032, 0x4000, X, Descriptor),               // Study Comments
+    (0x0038, 0x0004, X, Never),                    // Referenced Patient Alias Sequence
+    (0x0038, 0x0010, X, Never),                    // Admission ID
+    (0x0038, 0x0011, X, Never),                    // Issuer of Admission ID
+    (0x0038, 0x0014, X, Never),                    // Issuer of Admission ID Sequence
+    (0x0038, 0x001A, X, Temporal),                 // Scheduled Admission Date
+    (0x0038, 0x001B, X, Temporal),                 // Scheduled Admission Time
+    (0x0038, 0x001C, X, Temporal),                 // Scheduled Discharge Date
+    (0x0038, 0x001D, X, Temporal),                 // Scheduled Discharge Time
+    (0x0038, 0x001E, X, Never),                    // Scheduled Patient Institution Residence
+    (0x0038, 0x0020, X, Temporal),                 // Admitting Date
+    (0x0038, 0x0021, X, Temporal),                 // Admitting Time
+    (0x0038, 0x0030, X, Temporal),                 // Discharge Date
+    (0x0038, 0x0032, X, Temporal),                 // Discharge Time
+    (0x0038, 0x0040, X, Descriptor),               // Discharge Diagnosis Description
+    (0x0038, 0x0050, X, Never),                    // Special Needs
+    (0x0038, 0x0060, X, Never),                    // Service Episode ID
+    (0x0038, 0x0061, X, Never),                    // Issuer of Service Episode ID
+    (0x0038, 0x0062, X, Descriptor),               // Service Episode Description
+    (0x0038, 0x0064, X, Never),                    // Issuer of Service Episode ID Sequence
+    (0x0038, 0x0300, X, Never),                    // Current Patient Location
+    (0x0038, 0x0400, X, Never),                    // Patient's Institution Residence
+    (0x0038, 0x0500, X, Descriptor),               // Patient State
+    (0x0038, 0x4000, X, Descriptor),               // Visit Comments
+    (0x0040, 0x0001, X, Device),                   // Scheduled Station AE Title
+    (0x0040, 0x0002, X, Temporal),                 // Scheduled Procedure Step Start Date
+    (0x0040, 0x0003, X, Temporal),                 // Scheduled Procedure Step Start Time
+    (0x0040, 0x0004, X, Temporal),                 // Scheduled Procedure Step End Date
+    (0x0040, 0x0005, X, Temporal),                 // Scheduled Procedure Step End Time
+    (0x0040, 0x0006, X, Never),                    // Scheduled Performing Physician's Name
+    (0x0040, 0x0007, X, Descriptor),               // Scheduled Procedure Step Description
+    (0x0040, 0x000B, X, Never),                    // Scheduled Performing Physician Identification Sequence
+    (0x0040, 0x0010, X, Device),                   // Scheduled Station Name
+    (0x0040, 0x0011, X, Device),                   // Scheduled Procedure Step Location
+    (0x0040, 0x0012, X, Descriptor),               // Pre-Medication
+    (0x0040, 0x0241, X, Device),                   // Performed Station AE Title
+    (0x0040, 0x0242, X, Device),                   // Performed Station Name
+    (0x0040, 0x0243, X, Device),                   // Performed Location
+    (0x0040, 0x0244, X, Temporal),                 // Performed Procedure Step Start Date
+    (0x0040, 0x0245, X, Temporal),                 // Performed Procedure Step Start Time
+    (0x0040, 0x0250, X, Temporal),                 // Performed Procedure Step End Date
+    (0x0040, 0x0251, X, Temporal),                 // Performed Procedure Step End Time
+    (0x0040, 0x0253, X, Never),                    // Performed Procedure Step ID
+    (0x0040, 0x0254, X, Descriptor),               // Performed Procedure Step Description
+    (0x0040, 0x0275, X, Never),                    // Request Attributes Sequence
+    (0x0040, 0x0280, X, Descriptor),               // Comments on the Performed Procedure Step
+    (0x0040, 0x0513, X, Never),                    // Issuer of the Container Identifier Sequence
+    (0x0040, 0x0551, X, Never),                    // Specimen Identifier
+    (0x0040, 0x0555, X, Never),                    // Acquisition Context Sequence
+    (0x0040, 0x0562, X, Never),                    // Issuer of the Specimen Identifier Sequence
+    (0x0040, 0x0600, X, Descriptor),               // Specimen Short Description
+    (0x0040, 0x0602, X, Descriptor),               // Specimen Detailed Description
+    (0x0040, 0x0610, X, Never),                    // Specimen Preparation Sequence
+    (0x0040, 0x06FA, X, Never),                    // Slide Identifier
+    (0x0040, 0x1001, X, Never),                    // Requested Procedure ID
+    (0x0040, 0x1004, X, Never),                    // Patient Transport Arrangements
+    (0x0040, 0x1005, X, Never),                    // Requested Procedure Location
+    (0x0040, 0x1010, X, Never),                    // Names of Intended Recipients of Results
+    (0x0040, 0x1011, X, Never),                    // Intended Recipients of Results Identification Sequence
+    (0x0040, 0x1101, X, Never),                    // Person Identification Code Sequence
+    (0x0040, 0x1102, X, Never),                    // Person's Address
+    (0x0040, 0x1103, X, Never),                    // Person's Telephone Numbers
+    (0x0040, 0x1104, X, Never),                    // Person's Telecom Information
+    (0x0040, 0x1400, X, Descriptor),               // Requested Procedure Comments
+    (0x0040, 0x2001, X, Descriptor),               // Reason for the Imaging Service Request
+    (0x0040, 0x2004, X, Temporal),                 // Issue Date of Imaging Service Request
+    (0x0040, 0x2005, X, Temporal),                 // Issue Time of Imaging Service Request
+    (0x0040, 0x2008, X, Never),                    // Order Entered By
+    (0x0040, 0x2009, X, Never),                    // Order Enterer's Location
+    (0x0040, 0x2010, X, Never),                    // Order Callback Phone Number
+    (0x0040, 0x2011, X, Never),                    // Order Callback Telecom Information
+    (0x0040, 0x2016, Z, Never),                    // Placer Order Number / Imaging Service Request
+    (0x0040, 0x2017, Z, Never),                    // Filler Order Number / Imaging Service Request
+    (0x0040, 0x2400, X, Descriptor),               // Imaging Service Request Comments
+    (0x0040, 0x3001, X, Never),                    // Confidentiality Constraint on Patient Data Description
+    (0x0040, 0x4005, X, Temporal),                 // Scheduled Procedure Step Start DateTime
+    (0x0040, 0x4010, X, Temporal),                 // Scheduled Procedure Step Modification DateTime
+    (0x0040, 0x4011, X, Temporal),                 // Expected Completion DateTime
+    (0x0040, 0x4023, U, Uid),                      // Referenced General Purpose Scheduled Procedure Step Transaction UID
+    (0x0040, 0x4025, X, Device),                   // Scheduled Station Name Code Sequence
+    (0x0040, 0x4027, X, Device),                   // Scheduled Station Geographic Location Code Sequence
+    (0x0040, 0x4028, X, Device),                   // Performed Station Name Code Sequence
+    (0x0040, 0x4030, X, Device),                   // Performed Station Geographic Location Code Sequence
+    (0x0040, 0x4034, X, Never),                    // Scheduled Human Performers Sequence
+    (0x0040, 0x4035, X, Never),                    // Actual Human Performers Sequence
+    (0x0040, 0x4036, X, Never),                    // Human Performer's Organization
+    (0x0040, 0x4037, X, Never),                    // Human Performer's Name
+    (0x0040, 0x4050, X, Temporal),                 // Performed Procedure Step Start DateTime
+    (0x0040, 0x4051, X, Temporal),                 // Performed Procedure Step End DateTime
+    (0x0040, 0x4052, X, Temporal),                 // Procedure Step Cancellation DateTime
+    (0x0040, 0xA027, X, Institution),              // Verifying Organization
+    (0x0040, 0xA030, D, Temporal),                 // Verification DateTime
+    (0x0040, 0xA032, X, Temporal),                 // Observation DateTime
+    (0x0040, 0xA073, D, Never),                    // Verifying Observer Sequence
+    (0x0040, 0xA075, D, Never),                    // Verifying Observer Name
+    (0x0040, 0xA078, X, Never),                    // Author Observer Sequence
+    (0x0040, 0xA07A, X, Never),                    // Participant Sequence
+    (0x0040, 0xA07C, X, Never),                    // Custodial Organization Sequence
+    (0x0040, 0xA088, Z, Never),                    // Verifying Observer Identification Code Sequence
+    (0x0040, 0xA120, X, Temporal),                 // DateTime
+    (0x0040, 0xA121, D, Temporal),                 // Date
+    (0x0040, 0xA122, D, Temporal),                 // Time
+    (0x0040, 0xA123, D, Never),                    // Person Name
+    (0x0040, 0xA124, U, Uid),                      // UID
+    (0x0040, 0xA13A, D, Temporal),                 // Referenced DateTime
+    (0x0040, 0xA171, U, Uid),                      // Observation UID
+    (0x0040, 0xA172, U, Uid),                      // Referenced Observation UID (Trial)
+    (0x0040, 0xA192, X, Temporal),                 // Observation Date (Trial)
+    (0x0040, 0xA193, X, Temporal),                 // Observation Time (Trial)
+    (0x0040, 0xA352, X, Never),                    // Verbal Source (Trial)
+    (0x0040, 0xA353, X, Never),                    // Address (Trial)
+    (0x0040, 0xA354, X, Never),                    // Telephone Number (Trial)
+    (0x0040, 0xA358, X, Never),                    // Verbal Source Identifier Code Sequence (Trial)
+    (0x0040, 0xA402, U, Uid),                      // Observation Subject UID (Trial)
+    (0x0040, 0xA730, X, StructuredContent),        // Content Sequence
+    (0x0040, 0xDB06, X, Temporal),                 // Template Version
+    (0x0040, 0xDB07, X, Temporal),                 // Template Local Version
+    (0x0040, 0xDB0C, U, Uid),                      // Template Extension Organization UID
+    (0x0040, 0xDB0D, U, Uid),                      // Template Extension Creator UID
+    (0x0050, 0x001B, X, Never),                    // Container Component ID
+    (0x0050, 0x0020, X, Device),                   // Device Description
+    (0x0070, 0x0001, D, Graphics),                 // Graphic Annotation Sequence
+    (0x0070, 0x0084, Z, Never),                    // Content Creator's Name
+    (0x0070, 0x0086, X, Never),                    // Content Creator's Identification Code Sequence
+    (0x0070, 0x031A, U, Uid),                      // Fiducial UID
+    (0x0088, 0x0140, U, Uid),                      // Storage Media File-set UID
+    (0x0088, 0x0200, X, Never),                    // Icon Image Sequence
+    (0x0088, 0x0904, X, Never),                    // Topic Title
+    (0x0088, 0x0906, X, Never),                    // Topic Subject
+    (0x0088, 0x0910, X, Never),                    // Topic Author
+    (0x0088, 0x0912, X, Never),                    // Topic Keywords
+    (0x0400, 0x0100, X, Never),                    // Digital Signature UID
+    (0x0400, 0x0402, X, Never),                    // Referenced Digital Signature Sequence
+    (0x0400, 0x0403, X, Never),                    // Referenced SOP Instance MAC Sequence
+    (0x0400, 0x0404, X, Never),                    // MAC
+    (0x0400, 0x0550, X, Never),                    // Modified Attributes Sequence
+    (0x0400, 0x0561, X, Never),                    // Original Attributes Sequence
+    (0x2030, 0x0020, X, Never),                    // Text String
+    (0x3006, 0x0002, D, Never),                    // Structure Set Label
+    (0x3006, 0x0004, X, Never),                    // Structure Set Name
+    (0x3006, 0x0006, X, Descriptor),               // Structure Set Description
+    (0x3006, 0x0008, Z, Temporal),                 // Structure Set Date
+    (0x3006, 0x0009, Z, Temporal),                 // Structure Set Time
+    (0x3006, 0x0024, U, Uid),                      // Referenced Frame of Reference UID
+    (0x3006, 0x00C2, U, Uid),                      // Related Frame of Reference UID
+    (0x300A, 0x0002, D, Never),                    // RT Plan Label
+    (0x300A, 0x0003, X, Never),                    // RT Plan Name
+    (0x300A, 0x0004, X, Descriptor),               // RT Plan Description
+    (0x300A, 0x0006, X, Temporal),                 // RT Plan Date
+    (0x300A, 0x0007, X, Temporal),                 // RT Plan Time
+    (0x4008, 0x0114, X, Never),                    // Physician Approving Interpretation
+    (0x4008, 0x0119, X, Never),                    // Distribution Name
+    (0x4008, 0x011A, X, Never),                    // Distribution Address
+    (0x4008, 0x0202, X, Never),                    // Interpretation ID Issuer
+    (0x4008, 0x0300, X, Descriptor),               // Impressions
+    (0x4008, 0x4000, X, Descriptor),               // Results Comments
+    (0xFFFA, 0xFFFA, X, Never),                    // Digital Signatures Sequence
+    (0xFFFC, 0xFFFC, X, Never),                    // Data Set Trailing Padding
+];
+
+static ATTRIBUTE_MAP: LazyLock<HashMap<Tag, (ProfileAction, Retention)>> = LazyLock::new(|| {
+    ATTRIBUTES
+        .iter()
+        .map(|&(group, element, action, retention)| (Tag(group, element), (action, retention)))
+        .collect()
+});
+
+fn repeating_group_attribute(tag: Tag) -> Option<(ProfileAction, Retention)> {
+    match (tag.group() & 0xFF00, tag.element()) {
+        (0x5000, _) => Some((X, Never)),           // Curve Data
+        (0x6000, 0x3000) => Some((X, Graphics)),   // Overlay Data
+        (0x6000, 0x4000) => Some((X, Descriptor)), // Overlay Comments
+        _ => None,
+    }
+}
+
+impl Deidentification {
+    pub fn action(&self, tag: Tag) -> Option<ProfileAction> {
+        // Private attributes are removed, the Retain Safe Private option is not supported.
+        if tag.group() % 2 == 1 {
+            return Some(X);
+        }
+        let (action, retention) = ATTRIBUTE_MAP
+            .get(&tag)
+            .copied()
+            .or_else(|| repeating_group_attribute(tag))?;
+        let has = |option| self.options.contains(&option);
+        let retained = match retention {
+            Temporal if has(ProfileOption::RetainLongitudinalTemporalInformationFullDates) => {
+                Some(K)
+            }
+            Temporal if has(ProfileOption::RetainLongitudinalTemporalInformationModifiedDates) => {
+                Some(C)
+            }
+            Uid if has(ProfileOption::RetainUids) => Some(K),
+            Device if has(ProfileOption::RetainDeviceIdentity) => Some(K),
+            Institution if has(ProfileOption::RetainInstitutionIdentity) => Some(K),
+            PatientCharacteristics if has(ProfileOption::RetainPatientCharacteristics) => Some(K),
+            Descriptor if has(ProfileOption::CleanDescriptors) => Some(C),
+            StructuredContent if has(ProfileOption::CleanStructuredContent) => Some(C),
+            Graphics if has(ProfileOption::CleanGraphics) => Some(C),
+            _ => None,
+        };
+        Some(retained.unwrap_or(action))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(options: &[ProfileOption]) -> Deidentification {
+        Deidentification {
+            options: options.iter().copied().collect(),
+        }
+    }
+
+    #[test]
+    fn attributes_are_sorted_and_unique() {
+        for pair in ATTRIBUTES.windows(2) {
+            let (a, b) = ((pair[0].0, pair[0].1), (pair[1].0, pair[1].1));
+            assert!(a < b, "({:04X},{:04X}) is out of order", b.0, b.1);
+        }
+    }
+
+    #[test]
+    fn basic_profile_actions() {
+        let basic = profile(&[]);
+        let action = |group, element| basic.action(Tag(group, element));
+        assert_eq!(action(0x0010, 0x0010), Some(Z)); // Patient's Name
+        assert_eq!(action(0x0010, 0x0020), Some(Z)); // Patient ID
+        assert_eq!(action(0x0010, 0x0032), Some(X)); // Patient's Birth Time
+        assert_eq!(action(0x0040, 0xA123), Some(D)); // Person Name
+        assert_eq!(action(0x0020, 0x000D), Some(U)); // Study Instance UID
+        assert_eq!(action(0x0008, 0x0020), Some(Z)); // Study Date
+        assert_eq!(action(0x0008, 0x0080), Some(X)); // Institution Name
+        assert_eq!(action(0x0040, 0xA730), Some(X)); // Content Sequence
+        assert_eq!(action(0x0008, 0x0060), None); // Modality
+    }
+
+    #[test]
+    fn private_and_repeating_group_attributes() {
+        let basic = profile(&[]);
+        assert_eq!(basic.action(Tag(0x0009, 0x1001)), Some(X));
+        assert_eq!(basic.action(Tag(0x5002, 0x3000)), Some(X));
+        assert_eq!(basic.action(Tag(0x6002, 0x3000)), Some(X));
+        assert_eq!(basic.action(Tag(0x6002, 0x4000)), Some(X));
+        assert_eq!(basic.action(Tag(0x6002, 0x0010)), None);
+        let clean = profile(&[
+            ProfileOption::CleanGraphics,
+            ProfileOption::CleanDescriptors,
+        ]);
+        assert_eq!(clean.action(Tag(0x6002, 0x3000)), Some(C));
+        assert_eq!(clean.action(Tag(0x6002, 0x4000)), Some(C));
+        assert_eq!(clean.action(Tag(0x5002, 0x3000)), Some(X));
+    }
+
+    #[test]
+    fn options_retain_or_clean_their_attributes() {
+        let cases = [
+            (ProfileOption::RetainUids, Tag(0x0020, 0x000D), K),
+            (ProfileOption::RetainDeviceIdentity, Tag(0x0018, 0x1000), K),
+            (
+                ProfileOption::RetainInstitutionIdentity,
+                Tag(0x0008, 0x0080),
+                K,
+            ),
+            (
+                ProfileOption::RetainPatientCharacteristics,
+                Tag(0x0010, 0x0040),
+                K,
+            ),
+            (
+                ProfileOption::RetainPatientCharacteristics,
+                Tag(0x0010, 0x1010),
+                K,
+            ),
+            (
+                ProfileOption::RetainLongitudinalTemporalInformationFullDates,
+                Tag(0x0008, 0x0020),
+                K,
+            ),
+            (
+                ProfileOption::RetainLongitudinalTemporalInformationModifiedDates,
+                Tag(0x0008, 0x0020),
+                C,
+            ),
+            (ProfileOption::CleanDescriptors, Tag(0x0008, 0x1030), C),
+            (
+                ProfileOption::CleanStructuredContent,
+                Tag(0x0040, 0xA730),
+                C,
+            ),
+            (ProfileOption::CleanGraphics, Tag(0x0070, 0x0001), C),
+        ];
+        for (option, tag, expected) in cases {
+            assert_eq!(profile(&[option]).action(tag), Some(expected), "{}", tag);
+        }
+    }
+
+    #[test]
+    fn options_leave_other_attributes_alone() {
+        let all = profile(&[
+            ProfileOption::RetainUids,
+            ProfileOption::RetainDeviceIdentity,
+            ProfileOption::RetainInstitutionIdentity,
+            ProfileOption::RetainPatientCharacteristics,
+            ProfileOption::RetainLongitudinalTemporalInformationFullDates,
+            ProfileOption::CleanDescriptors,
+            ProfileOption::CleanStructuredContent,
+            ProfileOption::CleanGraphics,
+        ]);
+        assert_eq!(all.action(Tag(0x0010, 0x0010)), Some(Z));
+        assert_eq!(all.action(Tag(0x0008, 0x0050)), Some(Z));
+        assert_eq!(all.action(Tag(0x0040, 0xA123)), Some(D));
+        assert_eq!(all.action(Tag(0x0009, 0x1001)), Some(X));
+    }
+
+    #[test]
+    fn full_dates_take_precedence_over_modified_dates() {
+        let both = profile(&[
+            ProfileOption::RetainLongitudinalTemporalInformationFullDates,
+            ProfileOption::RetainLongitudinalTemporalInformationModifiedDates,
+        ]);
+        assert_eq!(both.action(Tag(0x0008, 0x0020)), Some(K));
+    }
+}
@@ -1,13 +1,67 @@
+pub mod deidentification;
+pub mod pseudonymization;
+pub mod redaction;
+pub mod scrubbing;
+pub mod semantic;
+
+use deidentification::{Deidentification, ProfileAction, ProfileOption};
 use dicom::core::Tag;
+use pseudonymization::Pseudonymization;
+use redaction::{RedactionAction, RedactionRule};
+use scrubbing::Scrubbing;
 use serde::{Deserialize, Deserializer};
 use std::{collections::HashSet, error::Error, fs, path::Path};
 
-fn deserialize_tags<'de, D>(deserializer: D) -> Result<HashSet<Tag>, D::Error>
+#[derive(Deserialize, Hash, PartialEq, Eq)]
+pub struct PrivateTag {
+    pub creator: String,
+    pub group: u16,
+    pub offset: u8,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TagEntry {
+    Tag(u16, u16),
+    Private(PrivateTag),
+}
+
+#[derive(Default)]
+pub struct TagSet {
+    tags: HashSet<Tag>,
+    private_tags: HashSet<PrivateTag>,
+}
+
+impl TagSet {
+    pub fn contains(&self, tag: Tag, private_creator: Option<&str>) -> bool {
+        self.tags.contains(&tag)
+            || private_creator.is_some_and(|creator| {
+                self.private_tags.contains(&PrivateTag {
+                    creator: creator.to_string(),
+                    group: tag.group(),
+                    offset: (tag.element() & 0xFF) as u8,
+                })
+            })
+    }
+}
+
+fn deserialize_tags<'de, D>(deserializer: D) -> Result<TagSet, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let tuples: HashSet<(u16, u16)> = HashSet::deserialize(deserializer)?;
-    Ok(tuples.into_iter().map(|(g, e)| Tag(g, e)).collect())
+    let entries: Vec<TagEntry> = Vec::deserialize(deserializer)?;
+    let mut tag_set = TagSet::default();
+    for entry in entries {
+        match entry {
+            TagEntry::Tag(g, e) => {
+                tag_set.tags.insert(Tag(g, e));
+            }
+            TagEntry::Private(private_tag) => {
+                tag_set.private_tags.insert(private_tag);
+            }
+        }
+    }
+    Ok(tag_set)
 }
 
 #[derive(Deserialize)]
@@ -23,23 +77,79 @@ pub struct NonDicomConfigEntry {
     pub prefix: String,
 }
 
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentIdentity {
+    #[default]
+    SopInstanceUid,
+    PathHash,
+    ContentHash,
+}
+
 #[derive(Deserialize)]
 pub struct Config {
+    #[serde(default)]
+    pub document_identity: DocumentIdentity,
+    pub language: Option<String>,
     pub dicom: Vec<DicomConfigEntry>,
     pub non_dicom: Vec<NonDicomConfigEntry>,
     pub fallback: NonDicomConfigEntry,
-    pub forbidden_code_meanings: HashSet<String>,
+    #[serde(default)]
+    pub redaction_rules: Vec<RedactionRule>,
     #[serde(deserialize_with = "deserialize_tags")]
-    pub forbidden_dicom_tags: HashSet<Tag>,
+    pub forbidden_dicom_tags: TagSet,
+    pub deidentification: Option<Deidentification>,
+    pub pseudonymization: Option<Pseudonymization>,
+    pub scrubbing: Option<Scrubbing>,
 }
 
 impl Config {
     pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
-        let config_str = fs::read_to_string(path)?;
-        let config = toml::from_str(&config_str)?;
+        let config_str = fs::read_to_string(&path)?;
+        let mut config: Config = toml::from_str(&config_str)?;
+        if let Some(pseudonymization) = &mut config.pseudonymization {
+            let config_dir = path.as_ref().parent().unwrap_or(Path::new(""));
+            pseudonymization.load_key(config_dir)?;
+        }
+        if let Some(deidentification) = &config.deidentification
+            && !deidentification
+                .options
+                .contains(&ProfileOption::RetainUids)
+            && config.pseudonymization.is_none()
+        {
+            return Err(
+                "De-identification replaces UIDs with keyed UIDs and requires [pseudonymization] \
+                 unless retain_uids is set"
+                    .into(),
+            );
+        }
+        for rule in &config.redaction_rules {
+            rule.validate()?;
+            if rule.action == RedactionAction::Hash && config.pseudonymization.is_none() {
+                return Err(
+                    "Redaction action 'hash' requires the key of [pseudonymization]".into(),
+                );
+            }
+        }
         Ok(config)
     }
 
+    // Forbidden tags are replaced by a dummy value, regardless of the de-identification profile.
+    pub fn tag_action(&self, tag: Tag, private_creator: Option<&str>) -> Option<ProfileAction> {
+        if self.forbidden_dicom_tags.contains(tag, private_creator) {
+            return Some(ProfileAction::D);
+        }
+        self.deidentification
+            .as_ref()
+            .and_then(|deidentification| deidentification.action(tag))
+    }
+
+    pub fn prefix_iri(&self, prefix: &str) -> Option<&str> {
+        self.to_prefix_iri_pairs()
+            .find(|(p, _)| *p == prefix)
+            .map(|(_, iri)| iri)
+    }
+
     pub fn to_prefix_iri_pairs(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
         let dicom = self
             .dicom
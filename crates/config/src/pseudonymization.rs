@@ -0,0 +1,174 @@
+use dicom::core::Tag;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::{error::Error, fs, path::Path, path::PathBuf};
+
+const MIN_KEY_LENGTH: usize = 32;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pseudonymization {
+    // Relative paths are resolved against the directory of the config file.
+    pub key_file: PathBuf,
+    // Enables shifting all dates of a patient by the same number of days, at most this many.
+    pub max_date_shift_days: Option<u32>,
+    #[serde(skip)]
+    key: Vec<u8>,
+}
+
+enum PseudonymKind {
+    Identifier(&'static str),
+    Uid,
+}
+
+fn pseudonym_kind(tag: Tag) -> Option<PseudonymKind> {
+    match (tag.group(), tag.element()) {
+        (0x0010, 0x0020) => Some(PseudonymKind::Identifier("patient_id")),
+        (0x0008, 0x0050) => Some(PseudonymKind::Identifier("accession_number")),
+        // Referenced and Media Storage SOP Instance UIDs must stay linked to the instances.
+        (0x0002, 0x0003) | (0x0008, 0x0018) | (0x0008, 0x1155) => Some(PseudonymKind::Uid),
+        (0x0020, 0x000D) | (0x0020, 0x000E) => Some(PseudonymKind::Uid),
+        // UIDREF content items and frames of reference usually repeat the UIDs above.
+        (0x0040, 0xA124) | (0x0020, 0x0052) | (0x3006, 0x0024) => Some(PseudonymKind::Uid),
+        _ => None,
+    }
+}
+
+impl Pseudonymization {
+    pub(crate) fn load_key(&mut self, config_dir: &Path) -> Result<(), Box<dyn Error>> {
+        let key_path = config_dir.join(&self.key_file);
+        let key = fs::read(&key_path)
+            .map_err(|e| format!("Failed to read key file '{}': {}", key_path.display(), e))?;
+        let key = key.trim_ascii().to_vec();
+        if key.len() < MIN_KEY_LENGTH {
+            return Err(format!(
+                "Key file '{}' must contain at least {} bytes",
+                key_path.display(),
+                MIN_KEY_LENGTH
+            )
+            .into());
+        }
+        self.key = key;
+        Ok(())
+    }
+
+    fn digest(&self, domain: &str, value: &str) -> [u8; 16] {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(domain.as_bytes());
+        mac.update(&[0]);
+        mac.update(value.trim().trim_end_matches('\0').as_bytes());
+        let mut digest = [0u8; 16];
+        digest.copy_from_slice(&mac.finalize().into_bytes()[..16]);
+        digest
+    }
+
+    fn hex_digest(&self, domain: &str, value: &str) -> String {
+        self.digest(domain, value)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    // Keyed hash for the "hash" redaction action, so that values cannot be recovered by hashing
+    // candidate values.
+    pub fn hash(&self, value: &str) -> String {
+        self.hex_digest("redaction", value)
+    }
+
+    pub fn uid(&self, uid: &str) -> String {
+        format!("2.25.{}", u128::from_be_bytes(self.digest("uid", uid)))
+    }
+
+    pub fn shifts_dates(&self) -> bool {
+        self.max_date_shift_days.is_some_and(|days| days > 0)
+    }
+
+    pub fn date_shift_days(&self, patient_id: &str) -> Option<i64> {
+        let max_days = self.max_date_shift_days.filter(|&days| days > 0)?;
+        let digest =
+            u64::from_be_bytes(self.digest("date_shift", patient_id)[..8].try_into().ok()?);
+        let days = 1 + (digest >> 1) % max_days as u64;
+        Some(if digest & 1 == 0 {
+            days as i64
+        } else {
+            -(days as i64)
+        })
+    }
+
+    pub fn pseudonymizes(&self, tag: Tag) -> bool {
+        pseudonym_kind(tag).is_some()
+    }
+
+    pub fn pseudonym(&self, tag: Tag, value: &str) -> Option<String> {
+        Some(match pseudonym_kind(tag)? {
+            PseudonymKind::Identifier(domain) => self.hex_digest(domain, value),
+            PseudonymKind::Uid => self.uid(value),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pseudonymization(key: &[u8]) -> Pseudonymization {
+        Pseudonymization {
+            key_file: PathBuf::new(),
+            max_date_shift_days: None,
+            key: key.to_vec(),
+        }
+    }
+
+    #[test]
+    fn redaction_hashes_depend_on_the_key() {
+        let a = pseudonymization(&[b'a'; MIN_KEY_LENGTH]);
+        let b = pseudonymization(&[b'b'; MIN_KEY_LENGTH]);
+        assert_eq!(a.hash("Doe^John"), a.hash(" Doe^John "));
+        assert_ne!(a.hash("Doe^John"), b.hash("Doe^John"));
+        assert_eq!(a.hash("Doe^John"), "e42cbe8c0a83b53fca4dcb6f2868f3c7");
+    }
+
+    #[test]
+    fn identifiers_are_hmac_sha256_pseudonyms() {
+        let a = pseudonymization(&[b'a'; MIN_KEY_LENGTH]);
+        let b = pseudonymization(&[b'b'; MIN_KEY_LENGTH]);
+        let patient_id = Tag(0x0010, 0x0020);
+        let accession_number = Tag(0x0008, 0x0050);
+        assert_eq!(
+            a.pseudonym(patient_id, "12345 ").as_deref(),
+            Some("cfd0e9f14040bfbab4952243a4710a28")
+        );
+        assert_ne!(
+            a.pseudonym(patient_id, "12345"),
+            b.pseudonym(patient_id, "12345")
+        );
+        // Domains keep equal values of different attributes apart.
+        assert_ne!(
+            a.pseudonym(patient_id, "12345"),
+            a.pseudonym(accession_number, "12345")
+        );
+        assert_eq!(a.pseudonym(Tag(0x0010, 0x0010), "Doe^John"), None);
+    }
+
+    #[test]
+    fn uids_are_rendered_as_uuid_derived_uids() {
+        let a = pseudonymization(&[b'a'; MIN_KEY_LENGTH]);
+        let uid = a.uid("1.2.3\0");
+        assert_eq!(uid, "2.25.249694072435865595109075956134325680710");
+        assert!(uid.len() <= 64);
+        for tag in [
+            Tag(0x0002, 0x0003),
+            Tag(0x0008, 0x0018),
+            Tag(0x0008, 0x1155),
+            Tag(0x0020, 0x000D),
+            Tag(0x0020, 0x000E),
+            Tag(0x0020, 0x0052),
+            Tag(0x0040, 0xA124),
+            Tag(0x3006, 0x0024),
+        ] {
+            assert_eq!(a.pseudonym(tag, "1.2.3").as_deref(), Some(uid.as_str()));
+        }
+    }
+}
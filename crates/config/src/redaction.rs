@@ -0,0 +1,281 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionAction {
+    Redact,
+    Hash,
+    Truncate(usize),
+    DropSubtree,
+}
+
+// A rule either matches content items (by concept name and/or template) or data elements (by
+// tag path). All given criteria must match.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RedactionRule {
+    pub code_value: Option<String>,
+    pub coding_scheme: Option<String>,
+    pub code_meaning: Option<String>,
+    pub template: Option<String>,
+    pub tag_path: Option<String>,
+    pub action: RedactionAction,
+}
+
+pub struct ContentItem<'a> {
+    pub code_value: Option<&'a str>,
+    pub coding_scheme: Option<&'a str>,
+    pub code_meaning: Option<&'a str>,
+    pub template: Option<&'a str>,
+}
+
+fn matches(criterion: &Option<String>, value: Option<&str>) -> bool {
+    criterion
+        .as_deref()
+        .is_none_or(|criterion| value.is_some_and(|value| value.trim() == criterion))
+}
+
+impl RedactionRule {
+    fn is_content_item_rule(&self) -> bool {
+        self.code_value.is_some()
+            || self.coding_scheme.is_some()
+            || self.code_meaning.is_some()
+            || self.template.is_some()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match (self.is_content_item_rule(), &self.tag_path) {
+            (false, None) => Err("Redaction rule without match criteria".into()),
+            (true, Some(_)) => {
+                Err("Redaction rule combines content item criteria with a tag path".into())
+            }
+            (_, Some(tag_path)) => {
+                let valid = tag_path.trim_start_matches('/').split('/').all(|s| {
+                    s == "*" || (s.len() == 8 && s.chars().all(|c| c.is_ascii_hexdigit()))
+                });
+                if valid {
+                    Ok(())
+                } else {
+                    Err(format!("Invalid tag path '{}'", tag_path))
+                }
+            }
+            (true, None) => Ok(()),
+        }
+    }
+
+    fn matches_content_item(&self, item: &ContentItem) -> bool {
+        self.is_content_item_rule()
+            && matches(&self.code_value, item.code_value)
+            && matches(&self.coding_scheme, item.coding_scheme)
+            && matches(&self.code_meaning, item.code_meaning)
+            && matches(&self.template, item.template)
+    }
+
+    // Tag paths are slash-separated tags (GGGGEEEE) without item indices, for example
+    // "0040A730/0040A730/0040A160". Patterns match the end of a path unless they start with '/',
+    // and '*' matches any single tag.
+    fn matches_tag_path(&self, tag_path: &str) -> bool {
+        let Some(pattern) = &self.tag_path else {
+            return false;
+        };
+        let anchored = pattern.starts_with('/');
+        let pattern: Vec<&str> = pattern.trim_start_matches('/').split('/').collect();
+        let path: Vec<&str> = tag_path.split('/').collect();
+        if pattern.len() > path.len() || (anchored && pattern.len() != path.len()) {
+            return false;
+        }
+        pattern
+            .iter()
+            .zip(&path[path.len() - pattern.len()..])
+            .all(|(p, t)| *p == "*" || p.eq_ignore_ascii_case(t))
+    }
+}
+
+pub fn content_item_action(rules: &[RedactionRule], item: &ContentItem) -> Option<RedactionAction> {
+    rules
+        .iter()
+        .find(|rule| rule.matches_content_item(item))
+        .map(|rule| rule.action)
+}
+
+pub fn tag_path_action(rules: &[RedactionRule], tag_path: &str) -> Option<RedactionAction> {
+    rules
+        .iter()
+        .find(|rule| rule.matches_tag_path(tag_path))
+        .map(|rule| rule.action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(toml: &str) -> RedactionRule {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn item<'a>(
+        code_value: &'a str,
+        coding_scheme: &'a str,
+        code_meaning: &'a str,
+    ) -> ContentItem<'a> {
+        ContentItem {
+            code_value: Some(code_value),
+            coding_scheme: Some(coding_scheme),
+            code_meaning: Some(code_meaning),
+            template: None,
+        }
+    }
+
+    #[test]
+    fn tag_path_patterns_match_the_end_of_a_path() {
+        let rules = [rule(
+            r#"tag_path = "0040A730/0040A160"
+action = "redact""#,
+        )];
+        assert_eq!(
+            tag_path_action(&rules, "0040A730/0040A730/0040A160"),
+            Some(RedactionAction::Redact)
+        );
+        assert_eq!(
+            tag_path_action(&rules, "0040A730/0040A160"),
+            Some(RedactionAction::Redact)
+        );
+        assert_eq!(tag_path_action(&rules, "0040A160"), None);
+        assert_eq!(tag_path_action(&rules, "0040A730/0040A160/00080100"), None);
+    }
+
+    #[test]
+    fn anchored_tag_path_patterns_match_the_whole_path() {
+        let rules = [rule(
+            r#"tag_path = "/0040A730/0040A160"
+action = "hash""#,
+        )];
+        assert_eq!(
+            tag_path_action(&rules, "0040A730/0040A160"),
+            Some(RedactionAction::Hash)
+        );
+        assert_eq!(tag_path_action(&rules, "0040A730/0040A730/0040A160"), None);
+    }
+
+    #[test]
+    fn wildcards_match_a_single_tag_case_insensitively() {
+        let rules = [rule(
+            r#"tag_path = "0040a730/*/0040a160"
+action = { truncate = 3 }"#,
+        )];
+        assert_eq!(
+            tag_path_action(&rules, "0040A730/0040A730/0040A160"),
+            Some(RedactionAction::Truncate(3))
+        );
+        assert_eq!(tag_path_action(&rules, "0040A730/0040A160"), None);
+    }
+
+    #[test]
+    fn content_item_rules_require_all_criteria() {
+        let rules = [rule(
+            r#"code_value = "121071"
+coding_scheme = "DCM"
+action = "drop_subtree""#,
+        )];
+        assert_eq!(
+            content_item_action(&rules, &item("121071 ", "DCM", "Finding")),
+            Some(RedactionAction::DropSubtree)
+        );
+        assert_eq!(
+            content_item_action(&rules, &item("121071", "SCT", "Finding")),
+            None
+        );
+        let without_scheme = ContentItem {
+            coding_scheme: None,
+            ..item("121071", "", "Finding")
+        };
+        assert_eq!(content_item_action(&rules, &without_scheme), None);
+    }
+
+    #[test]
+    fn templates_match_content_items() {
+        let rules = [rule(
+            r#"template = "1500"
+action = "redact""#,
+        )];
+        let measurement = ContentItem {
+            template: Some("1500"),
+            ..item("126000", "DCM", "Imaging Measurement Report")
+        };
+        assert_eq!(
+            content_item_action(&rules, &measurement),
+            Some(RedactionAction::Redact)
+        );
+        assert_eq!(
+            content_item_action(&rules, &item("126000", "DCM", "Imaging Measurement Report")),
+            None
+        );
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let rules = [
+            rule(
+                r#"code_meaning = "Finding"
+action = "redact""#,
+            ),
+            rule(
+                r#"code_value = "121071"
+action = "drop_subtree""#,
+            ),
+        ];
+        assert_eq!(
+            content_item_action(&rules, &item("121071", "DCM", "Finding")),
+            Some(RedactionAction::Redact)
+        );
+    }
+
+    #[test]
+    fn rules_of_one_kind_do_not_match_the_other() {
+        let rules = [
+            rule(
+                r#"code_meaning = "Finding"
+action = "redact""#,
+            ),
+            rule(
+                r#"tag_path = "0040A160"
+action = "hash""#,
+            ),
+        ];
+        assert_eq!(tag_path_action(&rules[..1], "0040A160"), None);
+        assert_eq!(
+            content_item_action(&rules[1..], &item("121071", "DCM", "Finding")),
+            None
+        );
+    }
+
+    #[test]
+    fn validation_rejects_ambiguous_rules() {
+        assert!(rule(r#"action = "redact""#).validate().is_err());
+        assert!(
+            rule(
+                r#"code_meaning = "Finding"
+tag_path = "0040A160"
+action = "redact""#
+            )
+            .validate()
+            .is_err()
+        );
+        assert!(
+            rule(
+                r#"tag_path = "0040A16"
+action = "redact""#
+            )
+            .validate()
+            .is_err()
+        );
+        assert!(
+            rule(
+                r#"tag_path = "/0040A730/*/0040A160"
+action = "redact""#
+            )
+            .validate()
+            .is_ok()
+        );
+    }
+}
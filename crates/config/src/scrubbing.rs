@@ -0,0 +1,26 @@
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scrubbing {
+    #[serde(default)]
+    pub patterns: Vec<ScrubPattern>,
+}
+
+// Matches are replaced by "[label]".
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScrubPattern {
+    pub label: String,
+    #[serde(deserialize_with = "deserialize_regex")]
+    pub regex: Regex,
+}
+
+fn deserialize_regex<'de, D>(deserializer: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let pattern = String::deserialize(deserializer)?;
+    Regex::new(&pattern).map_err(serde::de::Error::custom)
+}
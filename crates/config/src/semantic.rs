@@ -0,0 +1,33 @@
+// Vocabulary shared by the native semantic mapping of convert and the CONSTRUCT queries.
+
+// Relationship Types linking a content item to its parent, other than CONTAINS and the context
+// relationships, which attach their values to a context node of the parent.
+pub const RELATIONSHIPS: [(&str, &str); 4] = [
+    ("HAS PROPERTIES", "rad:hasProperties"),
+    ("HAS CONCEPT MOD", "rad:hasConceptModifier"),
+    ("INFERRED FROM", "rad:inferredFrom"),
+    ("SELECTED FROM", "rad:selectedFrom"),
+];
+
+pub const CONTEXTS: [(&str, &str); 2] = [
+    ("HAS OBS CONTEXT", "rad:observationContext"),
+    ("HAS ACQ CONTEXT", "rad:acquisitionContext"),
+];
+
+// Classes of the documents and containers declaring a known template, by Mapping Resource and
+// Template Identifier.
+pub const TEMPLATES: [(&str, &str); 6] = [
+    ("DCMR/1500", "rad:MeasurementReport"),
+    ("DCMR/2000", "rad:BasicDiagnosticImagingReport"),
+    ("DCMR/10001", "rad:ProjectionXRayRadiationDoseReport"),
+    ("DCMR/10011", "rad:CTRadiationDoseReport"),
+    ("DCMR/10021", "rad:RadiopharmaceuticalRadiationDoseReport"),
+    ("DCMR/10030", "rad:PatientRadiationDoseReport"),
+];
+
+// Concept modifiers of a measurement which are also linked by a fixed predicate, whatever concept
+// name a template uses for them.
+pub const MODIFIERS: [(&str, &str); 2] = [
+    ("dcm:121401", "rad:derivation"),
+    ("sct:370129005", "rad:method"),
+];
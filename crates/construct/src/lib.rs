@@ -1,7 +1,8 @@
 use config::Config;
+use config::semantic::{CONTEXTS, MODIFIERS, RELATIONSHIPS, TEMPLATES};
 use itertools::Itertools;
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct ConstructQuery {
     pub prefixes: Vec<String>,
     pub construct: Vec<String>,
@@ -10,9 +11,9 @@ pub struct ConstructQuery {
 
 struct ContainerResult {
     query: ConstructQuery,
-    next_iri_var: String,
-    next_level_index_var: String,
     next_level_var: String,
+    node_var: String,
+    subject_var: String,
 }
 
 #[derive(Clone)]
@@ -50,7 +51,11 @@ fn simple_queries(base: &ConstructQuery) -> Vec<MkQueryResult> {
         query: base
             .clone()
             .with_construct(vec![format!("?level0IRI {} ?object .", construct_pred)])
-            .with_where(vec![format!("?level0 {} ?object .", where_pred)]),
+            // Person names are nodes of their component groups, not values to copy.
+            .with_where(vec![
+                format!("?level0 {} ?object .", where_pred),
+                String::from("FILTER(isLiteral(?object))"),
+            ]),
     })
     .to_vec()
 }
@@ -113,11 +118,40 @@ fn other_queries(base: &ConstructQuery) -> Vec<MkQueryResult> {
             "labels",
             base.clone()
                 .with_construct(vec![
+                    r#"rad:acquisitionContext rdfs:label "acquisition context"@en ."#,
+                    r#"rad:BasicDiagnosticImagingReport rdfs:label "Basic Diagnostic Imaging Report"@en ."#,
                     r#"rad:cdt rdfs:label "Content Date Time"@en ."#,
+                    r#"rad:CTRadiationDoseReport rdfs:label "CT Radiation Dose"@en ."#,
+                    r#"rad:CTRadiationDoseReport rdfs:subClassOf rad:RadiationDoseReport ."#,
+                    r#"rad:denominator rdfs:label "Rational Denominator Value"@en ."#,
+                    r#"rad:derivation rdfs:label "Derivation"@en ."#,
+                    r#"rad:frameOfReference rdfs:label "Frame of Reference"@en ."#,
+                    r#"rad:frame rdfs:label "Referenced Frame Number"@en ."#,
+                    r#"rad:hasConceptModifier rdfs:label "has concept modifier"@en ."#,
+                    r#"rad:hasProperties rdfs:label "has properties"@en ."#,
+                    r#"rad:image rdfs:label "Image"@en ."#,
+                    r#"rad:inferredFrom rdfs:label "inferred from"@en ."#,
+                    r#"rad:mappingResource rdfs:label "Mapping Resource"@en ."#,
+                    r#"rad:MeasurementReport rdfs:label "Measurement Report"@en ."#,
+                    r#"rad:method rdfs:label "Measurement Method"@en ."#,
+                    r#"rad:numerator rdfs:label "Rational Numerator Value"@en ."#,
+                    r#"rad:observationContext rdfs:label "observation context"@en ."#,
                     r#"rad:patient rdfs:label "patient"@en ."#,
+                    r#"rad:PatientRadiationDoseReport rdfs:label "Patient Radiation Dose"@en ."#,
+                    r#"rad:PatientRadiationDoseReport rdfs:subClassOf rad:RadiationDoseReport ."#,
+                    r#"rad:ProjectionXRayRadiationDoseReport rdfs:label "Projection X-Ray Radiation Dose"@en ."#,
+                    r#"rad:ProjectionXRayRadiationDoseReport rdfs:subClassOf rad:RadiationDoseReport ."#,
+                    r#"rad:qualifier rdfs:label "Numeric Value Qualifier"@en ."#,
+                    r#"rad:RadiationDoseReport rdfs:label "Radiation Dose Report"@en ."#,
+                    r#"rad:RadiopharmaceuticalRadiationDoseReport rdfs:label "Radiopharmaceutical Radiation Dose"@en ."#,
+                    r#"rad:RadiopharmaceuticalRadiationDoseReport rdfs:subClassOf rad:RadiationDoseReport ."#,
+                    r#"rad:segment rdfs:label "Referenced Segment Number"@en ."#,
+                    r#"rad:selectedFrom rdfs:label "selected from"@en ."#,
                     r#"rad:seriesDt rdfs:label "Series Date Time"@en ."#,
                     r#"rad:siuid rdfs:label "SOP Instance UID"@en ."#,
                     r#"rad:studyDt rdfs:label "Study Date Time"@en ."#,
+                    r#"rad:template rdfs:label "template"@en ."#,
+                    r#"rad:templateIdentifier rdfs:label "Template Identifier"@en ."#,
                     r#"rad:value rdfs:label "value"@en ."#,
                     r#"sct:71388002 rdfs:label "Procedure"@en ."#,
                 ])
@@ -145,12 +179,13 @@ fn other_queries(base: &ConstructQuery) -> Vec<MkQueryResult> {
                 		      "rdfs:comment ?comments .",
                 ])
                 .with_where(vec![
-                  "?level0 dicom2rdf:person_name ?pn .",
-                  "OPTIONAL { ?pn dicom2rdf:pn_family ?family . }",
-                  "OPTIONAL { ?pn dicom2rdf:pn_middle ?middle . }",
-                  "OPTIONAL { ?pn dicom2rdf:pn_given ?given . }",
-                  "OPTIONAL { ?pn dicom2rdf:pn_prefix ?prefix . }",
-                  "OPTIONAL { ?pn dicom2rdf:pn_prefix ?suffix . }",
+                  "?level0 dicom2rdf:00100010 ?pn .",
+                  "?pn dicom2rdf:pn_alphabetic ?pnAlphabetic .",
+                  "OPTIONAL { ?pnAlphabetic dicom2rdf:pn_family ?family . }",
+                  "OPTIONAL { ?pnAlphabetic dicom2rdf:pn_middle ?middle . }",
+                  "OPTIONAL { ?pnAlphabetic dicom2rdf:pn_given ?given . }",
+                  "OPTIONAL { ?pnAlphabetic dicom2rdf:pn_prefix ?prefix . }",
+                  "OPTIONAL { ?pnAlphabetic dicom2rdf:pn_suffix ?suffix . }",
                   "OPTIONAL { ?level0 dicom2rdf:00100020 ?identifier . }",
                   "OPTIONAL { ?level0 dicom2rdf:00100021 ?issuer . }",
                   "OPTIONAL { ?level0 dicom2rdf:00100030 ?birthDate . }",
@@ -212,6 +247,12 @@ fn other_queries(base: &ConstructQuery) -> Vec<MkQueryResult> {
                     "BIND(IRI(CONCAT(STR(?conceptNameCodingScheme), ENCODE_FOR_URI(?conceptNameCode))) AS ?type)",
                 ]),
         ),
+        (
+            "template",
+            base.clone()
+                .with_construct(template_construct("?level0"))
+                .with_where(template_where("?level0")),
+        ),
     ].map(|(name, query)| {
             MkQueryResult {
                 name: name.into(),
@@ -232,7 +273,7 @@ pub fn top_level_construct_queries(config: &Config) -> Vec<MkQueryResult> {
     let datetime_queries = datetime_queries(&base);
     let uid_queries = uid_queries(&base);
     let other_queries = other_queries(&base);
-    vec![simple_queries, datetime_queries, uid_queries, other_queries].concat()
+    [simple_queries, datetime_queries, uid_queries, other_queries].concat()
 }
 
 pub fn nested_construct_queries(config: &Config, max_nesting: u8) -> Vec<MkQueryResult> {
@@ -250,76 +291,228 @@ pub fn nested_construct_queries(config: &Config, max_nesting: u8) -> Vec<MkQuery
                 num_query(base.clone(), n),
                 text_query(base.clone(), n),
                 uidref_query(base.clone(), n),
+                temporal_query(
+                    base.clone(),
+                    n,
+                    "date",
+                    "DATE",
+                    "dicom2rdf:0040A121",
+                    &["xsd:date", "xsd:gYearMonth", "xsd:gYear"],
+                ),
+                temporal_query(
+                    base.clone(),
+                    n,
+                    "time",
+                    "TIME",
+                    "dicom2rdf:0040A122",
+                    &["xsd:time"],
+                ),
+                temporal_query(
+                    base.clone(),
+                    n,
+                    "datetime",
+                    "DATETIME",
+                    "dicom2rdf:0040A120",
+                    &["xsd:dateTime", "xsd:date", "xsd:gYearMonth", "xsd:gYear"],
+                ),
+                pname_query(base.clone(), n),
+                coord_query(base.clone(), n),
+                tcoord_query(base.clone(), n),
+                reference_query(base.clone(), n),
             ]
+            .into_iter()
+            .chain(
+                (0..max_nesting + 1)
+                    .map(|m| by_reference_query(base.clone(), n, m))
+                    .collect::<Vec<_>>(),
+            )
         })
         .collect()
 }
 
-fn container_query(base: ConstructQuery, nesting: u8) -> ContainerResult {
-    let construct = (1..nesting + 1).flat_map(|i| {
-        let i_predecessor = i - 1;
-        [
-            format!("?level{i_predecessor}IRI ?level{i_predecessor}to{i}Predicate ?level{i}IRI ."),
-            format!("?level{i_predecessor}to{i}Predicate rdfs:label ?level{i}ConceptNameMeaning ."),
-        ]
-    });
-    let next_level_index_var = format!("?level{}Index", nesting + 1);
-    let next_level_var = format!("?level{}", nesting + 1);
-    let where_clause = (1..nesting + 1)
+// Nested IF() mapping the term in `var` to an IRI, unbound if nothing matches.
+fn term_mapping<T: AsRef<str>>(var: &str, cases: &[(T, &str)]) -> String {
+    cases
+        .iter()
+        .rev()
+        .fold(String::from("?unmapped"), |otherwise, (term, iri)| {
+            format!("IF({var} = {}, {iri}, {otherwise})", term.as_ref())
+        })
+}
+
+// Nested IF() mapping the string in `var` to an IRI, unbound if nothing matches.
+fn mapping(var: &str, cases: &[(&str, &str)]) -> String {
+    let cases = cases
+        .iter()
+        .map(|(value, iri)| (format!("\"{value}\""), *iri))
+        .collect::<Vec<_>>();
+    term_mapping(var, &cases)
+}
+
+// The template declared in the Content Template Sequence of the item at `level`.
+fn template_construct(level: &str) -> Vec<String> {
+    vec![
+        format!("{level}IRI rad:template {level}Template ."),
+        format!("{level}Template rad:mappingResource {level}MappingResource ;"),
+        format!("    rad:templateIdentifier {level}TemplateIdentifier ."),
+        format!("{level}IRI a {level}TemplateClass ."),
+    ]
+}
+
+fn template_where(level: &str) -> Vec<String> {
+    vec![
+        String::from("OPTIONAL {"),
+        format!("  {level} dicom2rdf:0040A504 ["),
+        format!("    dicom2rdf:00080105 {level}MappingResource ;"),
+        format!("    dicom2rdf:0040DB00 {level}TemplateIdentifier"),
+        String::from("  ] ."),
+        String::from("}"),
+        String::from("BIND(IRI(CONCAT("),
+        String::from("  STR(rad:),"),
+        String::from("  \"template/\","),
+        format!("  ENCODE_FOR_URI({level}MappingResource),"),
+        String::from("  \"/\","),
+        format!("  ENCODE_FOR_URI({level}TemplateIdentifier)"),
+        format!(")) AS {level}Template)"),
+        format!(
+            "BIND(CONCAT({level}MappingResource, \"/\", {level}TemplateIdentifier) AS {level}TemplateKey)"
+        ),
+        format!(
+            "BIND({} AS {level}TemplateClass)",
+            mapping(&format!("{level}TemplateKey"), &TEMPLATES)
+        ),
+    ]
+}
+
+// Every content item with a concept name is a node: containers are named after their concept
+// name, other items after their index. The chain descends through all of them from `{level}0`,
+// but only containers link to their children by concept name.
+fn chain_construct(level: &str, nesting: u8) -> Vec<String> {
+    (1..nesting + 1)
         .flat_map(|i| {
             let i_predecessor = i - 1;
             [
-                format!("?level{i_predecessor} dicom2rdf:0040A730 ["),
-                format!("    dicom2rdf:index ?level{i}Index ;"),
-                format!("    dicom2rdf:item ?level{i} ;"),
-                format!("] ."),
-                format!("?level{i} dicom2rdf:0040A040 \"CONTAINER\" ."),
-                format!("?level{i} dicom2rdf:0040A043 ["),
-                format!("  dicom2rdf:00080100 ?level{i}ConceptNameCode ;"),
-                format!("  dicom2rdf:00080102 ?level{i}ConceptNameCodingScheme ;"),
-                format!("  dicom2rdf:00080104 ?level{i}ConceptNameMeaning"),
-                format!("] ."),
-                format!("BIND(IRI(CONCAT("),
-                format!("  STR(?level{i}ConceptNameCodingScheme),"),
-                format!("  ENCODE_FOR_URI(STR(?level{i}ConceptNameCode))"),
-                format!(")) AS ?level{i_predecessor}to{i}Predicate)"),
-                format!("BIND(IRI(CONCAT("),
-                format!("  STR(?level{i_predecessor}IRI),"),
-                format!("  \"_\","),
-                format!("  STR(?level{i}Index),"),
-                format!("  \"_\","),
-                format!("  ENCODE_FOR_URI(?level{i}ConceptNameMeaning)"),
-                format!(")) AS ?level{i}IRI)"),
+                format!(
+                    "{level}{i_predecessor}IRI {level}{i_predecessor}to{i}Predicate {level}{i}IRI ."
+                ),
+                format!(
+                    "{level}{i_predecessor}to{i}Predicate rdfs:label {level}{i}ConceptNameMeaning ."
+                ),
+                format!(
+                    "{level}{i_predecessor}IRI {level}{i_predecessor}to{i}Relationship {level}{i}IRI ."
+                ),
             ]
+            .into_iter()
+            .chain(template_construct(&format!("{level}{i}")))
         })
-        .chain([
-            format!("?level{} dicom2rdf:0040A730 [", nesting),
-            format!("  dicom2rdf:index {} ;", next_level_index_var),
-            format!("  dicom2rdf:item {}", next_level_var),
-            format!("] ."),
-        ]);
+        .collect()
+}
+
+fn chain_where(level: &str, nesting: u8) -> Vec<String> {
+    (1..nesting + 1)
+        .flat_map(|i| {
+            let i_predecessor = i - 1;
+            [
+                format!("{level}{i_predecessor} dicom2rdf:0040A730 ["),
+                format!("    dicom2rdf:index {level}{i}Index ;"),
+                format!("    dicom2rdf:item {level}{i} ;"),
+                String::from("] ."),
+                format!("{level}{i} dicom2rdf:0040A040 {level}{i}ValueType ."),
+                format!("{level}{i} dicom2rdf:0040A043 ["),
+                format!("  dicom2rdf:00080100 {level}{i}ConceptNameCode ;"),
+                format!("  dicom2rdf:00080102 {level}{i}ConceptNameCodingScheme ;"),
+                format!("  dicom2rdf:00080104 {level}{i}ConceptNameMeaning"),
+                String::from("] ."),
+                format!(
+                    "OPTIONAL {{ {level}{i} dicom2rdf:0040A010 {level}{i}RelationshipType . }}"
+                ),
+                String::from("BIND(IF("),
+                format!("  {level}{i}ValueType = \"CONTAINER\","),
+                String::from("  IRI(CONCAT("),
+                format!("    STR({level}{i}ConceptNameCodingScheme),"),
+                format!("    ENCODE_FOR_URI(STR({level}{i}ConceptNameCode))"),
+                String::from("  )),"),
+                String::from("  ?unmapped"),
+                format!(") AS {level}{i_predecessor}to{i}Predicate)"),
+                String::from("BIND(IRI(CONCAT("),
+                format!("  STR({level}{i_predecessor}IRI),"),
+                String::from("  \"_\","),
+                format!("  STR({level}{i}Index),"),
+                format!("  IF({level}{i}ValueType = \"CONTAINER\","),
+                format!("     CONCAT(\"_\", ENCODE_FOR_URI({level}{i}ConceptNameMeaning)), \"\")"),
+                format!(")) AS {level}{i}IRI)"),
+                format!(
+                    "BIND({} AS {level}{i_predecessor}to{i}Relationship)",
+                    mapping(&format!("{level}{i}RelationshipType"), &RELATIONSHIPS)
+                ),
+            ]
+            .into_iter()
+            .chain(template_where(&format!("{level}{i}")))
+        })
+        .collect()
+}
+
+fn container_query(base: ConstructQuery, nesting: u8) -> ContainerResult {
+    let construct = chain_construct("?level", nesting).into_iter().chain([
+        format!("?level{nesting}IRI ?contextPredicate ?subjectIRI ."),
+        format!("?level{nesting}IRI ?relationshipPredicate ?nodeIRI ."),
+    ]);
+    let next_level_index_var = format!("?level{}Index", nesting + 1);
+    let next_level_var = format!("?level{}", nesting + 1);
+    let where_clause = chain_where("?level", nesting).into_iter().chain([
+        format!("?level{} dicom2rdf:0040A730 [", nesting),
+        format!("  dicom2rdf:index {} ;", next_level_index_var),
+        format!("  dicom2rdf:item {}", next_level_var),
+        String::from("] ."),
+        format!(
+            "OPTIONAL {{ {} dicom2rdf:0040A010 ?relationshipType . }}",
+            next_level_var
+        ),
+        format!(
+            "BIND(IRI(CONCAT(STR(?level{}IRI), \"_\", STR({}))) AS ?nodeIRI)",
+            nesting, next_level_index_var
+        ),
+        format!(
+            "BIND({} AS ?relationshipPredicate)",
+            mapping("?relationshipType", &RELATIONSHIPS)
+        ),
+        format!(
+            "BIND({} AS ?contextPredicate)",
+            mapping("?relationshipType", &CONTEXTS)
+        ),
+        String::from("BIND(IF("),
+        String::from("  BOUND(?contextPredicate),"),
+        format!(
+            "  IRI(CONCAT(STR(?level{}IRI), \"_\", STRAFTER(STR(?contextPredicate), STR(rad:)))),",
+            nesting
+        ),
+        format!("  ?level{}IRI", nesting),
+        String::from(") AS ?subjectIRI)"),
+    ]);
     ContainerResult {
         query: base.with_construct(construct).with_where(where_clause),
-        next_iri_var: format!("?level{}IRI", nesting),
-        next_level_index_var,
         next_level_var,
+        node_var: String::from("?nodeIRI"),
+        subject_var: String::from("?subjectIRI"),
     }
 }
 
 fn code_query(base: ConstructQuery, nesting: u8) -> MkQueryResult {
     let ContainerResult {
         query,
-        next_iri_var: iri_var,
-        next_level_index_var: _,
         next_level_var: level_var,
+        node_var,
+        subject_var,
     } = container_query(base, nesting);
     MkQueryResult {
         name: format!("code_{nesting}"),
         query: query
             .with_construct(vec![
-                format!("{iri_var} ?valuePred ?valueIRI ."),
+                format!("{subject_var} ?valuePred ?valueIRI ."),
+                format!("{node_var} rad:value ?valueIRI ."),
                 String::from("?valuePred rdfs:label ?conceptNameMeaning ."),
                 String::from("?valueIRI rdfs:label ?conceptMeaning ."),
+                format!("{subject_var} ?modifierPred ?valueIRI ."),
             ])
             .with_where(vec![
                 format!("{level_var} dicom2rdf:0040A040 \"CODE\" ."),
@@ -341,6 +534,10 @@ fn code_query(base: ConstructQuery, nesting: u8) -> MkQueryResult {
                 format!("  STR(?conceptCodingScheme),"),
                 format!("  ENCODE_FOR_URI(?conceptCode)"),
                 format!(")) AS ?valueIRI)"),
+                format!(
+                    "BIND(IF(?relationshipType = \"HAS CONCEPT MOD\", {}, ?unmapped) AS ?modifierPred)",
+                    term_mapping("?valuePred", &MODIFIERS)
+                ),
             ]),
     }
 }
@@ -348,15 +545,16 @@ fn code_query(base: ConstructQuery, nesting: u8) -> MkQueryResult {
 fn text_query(base: ConstructQuery, nesting: u8) -> MkQueryResult {
     let ContainerResult {
         query,
-        next_iri_var: iri_var,
-        next_level_index_var: _,
         next_level_var: level_var,
+        node_var,
+        subject_var,
     } = container_query(base, nesting);
     MkQueryResult {
         name: format!("text_{nesting}"),
         query: query
             .with_construct(vec![
-                format!("{iri_var} ?valuePred ?value ."),
+                format!("{subject_var} ?valuePred ?value ."),
+                format!("{node_var} rad:value ?value ."),
                 String::from("?valuePred rdfs:label ?conceptNameMeaning ."),
             ])
             .with_where(vec![
@@ -378,18 +576,21 @@ fn text_query(base: ConstructQuery, nesting: u8) -> MkQueryResult {
 fn num_query(base: ConstructQuery, nesting: u8) -> MkQueryResult {
     let ContainerResult {
         query,
-        next_iri_var: iri_var,
-        next_level_index_var,
         next_level_var,
+        node_var,
+        subject_var,
     } = container_query(base, nesting);
     MkQueryResult {
         name: format!("num_{nesting}"),
         query: query
             .with_construct(vec![
-                format!("{} ?valuePred ?valueIRI .", iri_var),
-                format!("?valueIRI a qudt:QuantityValue; qudt:numericValue ?value; qudt:unit ?valueUnitIRI ."),
+                format!("{} ?valuePred {} .", subject_var, node_var),
+                format!("{} a qudt:QuantityValue; qudt:numericValue ?value; qudt:unit ?valueUnitIRI .", node_var),
+                format!("{} rad:numerator ?numerator; rad:denominator ?denominator .", node_var),
+                format!("{} rad:qualifier ?qualifierIRI .", node_var),
                 format!("?valuePred rdfs:label ?conceptNameMeaning ."),
                 format!("?valueUnitIRI rdfs:label ?valueUnitCodeMeaning ."),
+                format!("?qualifierIRI rdfs:label ?qualifierCodeMeaning ."),
             ])
             .with_where(vec![
                 format!("{} dicom2rdf:0040A040 \"NUM\" .", next_level_var),
@@ -398,18 +599,31 @@ fn num_query(base: ConstructQuery, nesting: u8) -> MkQueryResult {
                 format!("  dicom2rdf:00080102 ?conceptNameCodingScheme ;"),
                 format!("  dicom2rdf:00080104 ?conceptNameMeaning"),
                 format!("] ."),
-                format!("{} dicom2rdf:0040A300 ?measuredValue .", next_level_var),
-                format!("?measuredValue dicom2rdf:004008EA ["),
-                format!("  dicom2rdf:00080100 ?valueUnitCode ;"),
-                format!("  dicom2rdf:00080102 ?valueUnitCodingScheme ;"),
-                format!("  dicom2rdf:00080104 ?valueUnitCodeMeaning"),
-                format!("] ."),
-                format!("OPTIONAL {{ ?measuredValue dicom2rdf:0040A161 ?fpValue }}"),
-                format!("OPTIONAL {{ ?measuredValue dicom2rdf:0040A30A ?numValue }}"),
+                format!("OPTIONAL {{"),
+                format!("  {} dicom2rdf:0040A300 ?measuredValue .", next_level_var),
+                format!("  OPTIONAL {{"),
+                format!("    ?measuredValue dicom2rdf:004008EA ["),
+                format!("      dicom2rdf:00080100 ?valueUnitCode ;"),
+                format!("      dicom2rdf:00080102 ?valueUnitCodingScheme ;"),
+                format!("      dicom2rdf:00080104 ?valueUnitCodeMeaning"),
+                format!("    ] ."),
+                format!("  }}"),
+                format!("  OPTIONAL {{ ?measuredValue dicom2rdf:0040A161 ?fpValue }}"),
+                format!("  OPTIONAL {{ ?measuredValue dicom2rdf:0040A30A ?numValue }}"),
+                format!("  OPTIONAL {{ ?measuredValue dicom2rdf:0040A162 ?numerator }}"),
+                format!("  OPTIONAL {{ ?measuredValue dicom2rdf:0040A163 ?denominator }}"),
+                format!("}}"),
+                format!("OPTIONAL {{"),
+                format!("  {} dicom2rdf:0040A301 [", next_level_var),
+                format!("    dicom2rdf:00080100 ?qualifierCode ;"),
+                format!("    dicom2rdf:00080102 ?qualifierCodingScheme ;"),
+                format!("    dicom2rdf:00080104 ?qualifierCodeMeaning"),
+                format!("  ] ."),
+                format!("}}"),
                 format!("BIND(COALESCE(xsd:decimal(?fpValue), xsd:decimal(?numValue)) AS ?value)"),
-                format!("FILTER(BOUND(?value))"),
+                format!("BIND(IRI(CONCAT(STR(?qualifierCodingScheme), ENCODE_FOR_URI(?qualifierCode))) AS ?qualifierIRI)"),
+                format!("FILTER(BOUND(?value) || BOUND(?qualifierIRI))"),
                 format!("BIND(IRI(CONCAT(STR(?conceptNameCodingScheme), ENCODE_FOR_URI(?conceptNameCode))) AS ?valuePred)"),
-                format!("BIND(IRI(CONCAT(STR({}), \"_\", STR({}))) AS ?valueIRI)", iri_var, next_level_index_var),
                 format!("BIND(IRI(CONCAT(STR(?valueUnitCodingScheme), ENCODE_FOR_URI(?valueUnitCode))) as ?valueUnitIRI)"),
             ]),
     }
@@ -418,15 +632,16 @@ fn num_query(base: ConstructQuery, nesting: u8) -> MkQueryResult {
 fn uidref_query(base: ConstructQuery, nesting: u8) -> MkQueryResult {
     let ContainerResult {
         query,
-        next_iri_var,
-        next_level_index_var: _,
         next_level_var,
+        node_var,
+        subject_var,
     } = container_query(base, nesting);
     MkQueryResult {
         name: format!("uidref_{nesting}"),
         query: query
             .with_construct(vec![
-                format!("{next_iri_var} ?valuePred ?valueIRI ."),
+                format!("{subject_var} ?valuePred ?valueIRI ."),
+                format!("{node_var} rad:value ?valueIRI ."),
                 String::from("?valuePred rdfs:label ?conceptNameMeaning ."),
             ])
             .with_where(vec![
@@ -443,6 +658,239 @@ fn uidref_query(base: ConstructQuery, nesting: u8) -> MkQueryResult {
     }
 }
 
+// DATE, TIME and DATETIME values are already typed in the raw layer. Values replaced by
+// de-identification are plain strings and left out.
+fn temporal_query(
+    base: ConstructQuery,
+    nesting: u8,
+    name: &str,
+    value_type: &str,
+    value_pred: &str,
+    datatypes: &[&str],
+) -> MkQueryResult {
+    let ContainerResult {
+        query,
+        next_level_var,
+        node_var,
+        subject_var,
+    } = container_query(base, nesting);
+    MkQueryResult {
+        name: format!("{name}_{nesting}"),
+        query: query
+            .with_construct(vec![
+                format!("{subject_var} ?valuePred ?value ."),
+                format!("{node_var} rad:value ?value ."),
+                String::from("?valuePred rdfs:label ?conceptNameMeaning ."),
+            ])
+            .with_where(vec![
+                format!("{next_level_var} dicom2rdf:0040A040 \"{value_type}\" ."),
+                format!("{next_level_var} dicom2rdf:0040A043 ["),
+                format!("  dicom2rdf:00080100 ?conceptNameCode ;"),
+                format!("  dicom2rdf:00080102 ?conceptNameCodingScheme ;"),
+                format!("  dicom2rdf:00080104 ?conceptNameMeaning"),
+                format!("] ."),
+                format!("{next_level_var} {value_pred} ?value ."),
+                format!("FILTER(DATATYPE(?value) IN ({}))", datatypes.join(", ")),
+                format!("BIND(IRI(CONCAT(STR(?conceptNameCodingScheme), ENCODE_FOR_URI(?conceptNameCode))) AS ?valuePred)"),
+            ]),
+    }
+}
+
+fn pname_query(base: ConstructQuery, nesting: u8) -> MkQueryResult {
+    let ContainerResult {
+        query,
+        next_level_var,
+        node_var,
+        subject_var,
+    } = container_query(base, nesting);
+    MkQueryResult {
+        name: format!("pname_{nesting}"),
+        query: query
+            .with_construct(vec![
+                format!("{subject_var} ?valuePred {node_var} ."),
+                format!("{node_var} a schema:Person ;"),
+                String::from("           schema:familyName ?family ;"),
+                String::from("           schema:additionalName ?middle ;"),
+                String::from("           schema:givenName ?given ;"),
+                String::from("           schema:honorificPrefix ?prefix ;"),
+                String::from("           schema:honorificSuffix ?suffix ."),
+                String::from("?valuePred rdfs:label ?conceptNameMeaning ."),
+            ])
+            .with_where(vec![
+                format!("{next_level_var} dicom2rdf:0040A040 \"PNAME\" ."),
+                format!("{next_level_var} dicom2rdf:0040A043 ["),
+                format!("  dicom2rdf:00080100 ?conceptNameCode ;"),
+                format!("  dicom2rdf:00080102 ?conceptNameCodingScheme ;"),
+                format!("  dicom2rdf:00080104 ?conceptNameMeaning"),
+                format!("] ."),
+                format!("{next_level_var} dicom2rdf:0040A123 ?pn ."),
+                format!("?pn dicom2rdf:pn_alphabetic ?pnAlphabetic ."),
+                format!("OPTIONAL {{ ?pnAlphabetic dicom2rdf:pn_family ?family . }}"),
+                format!("OPTIONAL {{ ?pnAlphabetic dicom2rdf:pn_middle ?middle . }}"),
+                format!("OPTIONAL {{ ?pnAlphabetic dicom2rdf:pn_given ?given . }}"),
+                format!("OPTIONAL {{ ?pnAlphabetic dicom2rdf:pn_prefix ?prefix . }}"),
+                format!("OPTIONAL {{ ?pnAlphabetic dicom2rdf:pn_suffix ?suffix . }}"),
+                format!("BIND(IRI(CONCAT(STR(?conceptNameCodingScheme), ENCODE_FOR_URI(?conceptNameCode))) AS ?valuePred)"),
+            ]),
+    }
+}
+
+// The raw layer already describes SCOORD and SCOORD3D coordinates as a WKT geometry. It is linked
+// to the images the coordinates are selected from, or to the Frame of Reference of SCOORD3D
+// coordinates.
+fn coord_query(base: ConstructQuery, nesting: u8) -> MkQueryResult {
+    let ContainerResult {
+        query,
+        next_level_var,
+        node_var,
+        subject_var,
+    } = container_query(base, nesting);
+    MkQueryResult {
+        name: format!("coord_{nesting}"),
+        query: query
+            .with_construct(vec![
+                format!("{subject_var} ?valuePred {node_var} ."),
+                format!("{node_var} a geo:Geometry ;"),
+                String::from("             geo:asWKT ?wkt ;"),
+                String::from("             rad:image ?imageIRI ;"),
+                String::from("             rad:frameOfReference ?frameOfReferenceIRI ."),
+                String::from("?valuePred rdfs:label ?conceptNameMeaning ."),
+            ])
+            .with_where(vec![
+                format!("{next_level_var} dicom2rdf:0040A040 ?valueType ."),
+                format!(r#"FILTER(?valueType IN ("SCOORD", "SCOORD3D"))"#),
+                format!("{next_level_var} dicom2rdf:0040A043 ["),
+                format!("  dicom2rdf:00080100 ?conceptNameCode ;"),
+                format!("  dicom2rdf:00080102 ?conceptNameCodingScheme ;"),
+                format!("  dicom2rdf:00080104 ?conceptNameMeaning"),
+                format!("] ."),
+                format!("{next_level_var} geo:hasGeometry/geo:asWKT ?wkt ."),
+                format!("OPTIONAL {{"),
+                format!("  {next_level_var} dicom2rdf:0040A730/dicom2rdf:item ?image ."),
+                format!(r#"  ?image dicom2rdf:0040A040 "IMAGE" ."#),
+                format!("  ?image dicom2rdf:00081199/dicom2rdf:00081155 ?imageUID ."),
+                format!("}}"),
+                format!("OPTIONAL {{ {next_level_var} dicom2rdf:30060024 ?frameOfReferenceUID . }}"),
+                format!(r#"BIND(IRI(CONCAT("urn:oid:", ?imageUID)) AS ?imageIRI)"#),
+                format!(r#"BIND(IRI(CONCAT("urn:oid:", ?frameOfReferenceUID)) AS ?frameOfReferenceIRI)"#),
+                format!("BIND(IRI(CONCAT(STR(?conceptNameCodingScheme), ENCODE_FOR_URI(?conceptNameCode))) AS ?valuePred)"),
+            ]),
+    }
+}
+
+// TCOORD items keep their time points as typed values: sample positions, time offsets as
+// xsd:duration and datetimes, linked to the images they are selected from.
+fn tcoord_query(base: ConstructQuery, nesting: u8) -> MkQueryResult {
+    let ContainerResult {
+        query,
+        next_level_var,
+        node_var,
+        subject_var,
+    } = container_query(base, nesting);
+    MkQueryResult {
+        name: format!("tcoord_{nesting}"),
+        query: query
+            .with_construct(vec![
+                format!("{subject_var} ?valuePred {node_var} ."),
+                format!("{node_var} rad:temporalRangeType ?rangeType ;"),
+                String::from("             rad:samplePosition ?samplePosition ;"),
+                String::from("             rad:timeOffset ?timeOffset ;"),
+                String::from("             rad:dateTime ?dateTime ;"),
+                String::from("             rad:image ?imageIRI ."),
+                String::from("?valuePred rdfs:label ?conceptNameMeaning ."),
+            ])
+            .with_where(vec![
+                format!(r#"{next_level_var} dicom2rdf:0040A040 "TCOORD" ."#),
+                format!("{next_level_var} dicom2rdf:0040A043 ["),
+                format!("  dicom2rdf:00080100 ?conceptNameCode ;"),
+                format!("  dicom2rdf:00080102 ?conceptNameCodingScheme ;"),
+                format!("  dicom2rdf:00080104 ?conceptNameMeaning"),
+                format!("] ."),
+                format!("{next_level_var} dicom2rdf:0040A130 ?rangeType ."),
+                format!("OPTIONAL {{ {next_level_var} dicom2rdf:0040A132 ?samplePosition . }}"),
+                format!("OPTIONAL {{ {next_level_var} dicom2rdf:time_offset ?timeOffset . }}"),
+                format!("OPTIONAL {{ {next_level_var} dicom2rdf:0040A13A ?dateTime . }}"),
+                format!("OPTIONAL {{"),
+                format!("  {next_level_var} dicom2rdf:0040A730/dicom2rdf:item ?image ."),
+                format!(r#"  ?image dicom2rdf:0040A040 "IMAGE" ."#),
+                format!("  ?image dicom2rdf:00081199/dicom2rdf:00081155 ?imageUID ."),
+                format!("}}"),
+                format!(r#"BIND(IRI(CONCAT("urn:oid:", ?imageUID)) AS ?imageIRI)"#),
+                format!("BIND(IRI(CONCAT(STR(?conceptNameCodingScheme), ENCODE_FOR_URI(?conceptNameCode))) AS ?valuePred)"),
+            ]),
+    }
+}
+
+// IMAGE, COMPOSITE and WAVEFORM items become a node linking the referenced SOP Instance and SOP
+// Class like the document itself, with the referenced frame and segment numbers.
+fn reference_query(base: ConstructQuery, nesting: u8) -> MkQueryResult {
+    let ContainerResult {
+        query,
+        next_level_var,
+        node_var,
+        subject_var,
+    } = container_query(base, nesting);
+    MkQueryResult {
+        name: format!("reference_{nesting}"),
+        query: query
+            .with_construct(vec![
+                format!("{subject_var} ?valuePred {node_var} ."),
+                format!("{node_var} rad:siuid ?instanceIRI ;"),
+                String::from("              dcm:110181 ?classIRI ;"),
+                String::from("              rad:frame ?frame ;"),
+                String::from("              rad:segment ?segment ."),
+                String::from("?valuePred rdfs:label ?conceptNameMeaning ."),
+            ])
+            .with_where(vec![
+                format!("{next_level_var} dicom2rdf:0040A040 ?valueType ."),
+                format!(r#"FILTER(?valueType IN ("IMAGE", "COMPOSITE", "WAVEFORM"))"#),
+                format!("{next_level_var} dicom2rdf:0040A043 ["),
+                format!("  dicom2rdf:00080100 ?conceptNameCode ;"),
+                format!("  dicom2rdf:00080102 ?conceptNameCodingScheme ;"),
+                format!("  dicom2rdf:00080104 ?conceptNameMeaning"),
+                format!("] ."),
+                format!("{next_level_var} dicom2rdf:00081199 ?referencedSop ."),
+                format!("?referencedSop dicom2rdf:00081155 ?instanceUID ."),
+                format!("OPTIONAL {{ ?referencedSop dicom2rdf:00081150 ?classUID . }}"),
+                format!("OPTIONAL {{ ?referencedSop dicom2rdf:00081160 ?frame . }}"),
+                format!("OPTIONAL {{ ?referencedSop dicom2rdf:0062000B ?segment . }}"),
+                format!(r#"BIND(IRI(CONCAT("urn:oid:", ?instanceUID)) AS ?instanceIRI)"#),
+                format!(r#"BIND(IRI(CONCAT("urn:oid:", ?classUID)) AS ?classIRI)"#),
+                format!("BIND(IRI(CONCAT(STR(?conceptNameCodingScheme), ENCODE_FOR_URI(?conceptNameCode))) AS ?valuePred)"),
+            ]),
+    }
+}
+
+// Items referencing another content item instead of nesting it link their parent to the node of
+// the target. The raw layer already resolves the Referenced Content Item Identifier to the target
+// item, whose node is found by a second chain of `target_nesting` levels from the root.
+fn by_reference_query(base: ConstructQuery, nesting: u8, target_nesting: u8) -> MkQueryResult {
+    let level_var = format!("?level{}", nesting + 1);
+    MkQueryResult {
+        name: format!("by_reference_{nesting}_{target_nesting}"),
+        query: base
+            .with_construct(chain_construct("?level", nesting))
+            .with_construct(vec![format!(
+                "?level{nesting}IRI ?relationshipPredicate ?target{target_nesting}IRI ."
+            )])
+            .with_where(chain_where("?level", nesting))
+            .with_where(vec![
+                String::from("BIND(?level0 AS ?target0)"),
+                String::from("BIND(?level0IRI AS ?target0IRI)"),
+            ])
+            .with_where(chain_where("?target", target_nesting))
+            .with_where(vec![
+                format!("?level{nesting} dicom2rdf:0040A730/dicom2rdf:item {level_var} ."),
+                format!("{level_var} dicom2rdf:referenced_content_item ?target{target_nesting} ."),
+                format!("OPTIONAL {{ {level_var} dicom2rdf:0040A010 ?relationshipType . }}"),
+                format!(
+                    "BIND({} AS ?relationshipPredicate)",
+                    mapping("?relationshipType", &RELATIONSHIPS)
+                ),
+            ]),
+    }
+}
+
 pub fn prefixes(config: &Config) -> Vec<String> {
     config
         .to_prefix_iri_pairs()
@@ -453,13 +901,7 @@ pub fn prefixes(config: &Config) -> Vec<String> {
 impl ConstructQuery {
     pub fn to_sparql(&self) -> String {
         let mut query = String::new();
-        query.push_str(
-            &self
-                .prefixes
-                .iter()
-                .map(|line| format!("{}", line))
-                .join("\n"),
-        );
+        query.push_str(&self.prefixes.iter().map(|line| line.to_string()).join("\n"));
         query.push_str("\nCONSTRUCT {\n");
         query.push_str(
             &self
@@ -0,0 +1,68 @@
+use dicom::core::value::Value;
+use dicom::object::mem::InMemElement;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use tempfile::NamedTempFile;
+
+// Value bytes of an OB, OD, OF, OL, OV, OW, UN or pixel data element. Encapsulated pixel data is kept as its
+// fragments, without the basic offset table.
+pub struct BulkData<'a> {
+    chunks: Vec<Cow<'a, [u8]>>,
+    pub fragment_count: Option<usize>,
+    pub byte_length: usize,
+    pub sha256: String,
+}
+
+impl<'a> BulkData<'a> {
+    pub fn from_element(data_element: &'a InMemElement) -> Result<Self, String> {
+        let (chunks, fragment_count) = match data_element.value() {
+            // Multi-byte values are hashed in the byte order they were decoded to.
+            Value::Primitive(value) => (vec![value.to_bytes()], None),
+            Value::PixelSequence(sequence) => {
+                let fragments = sequence.fragments();
+                (
+                    fragments.iter().map(|f| Cow::Borrowed(&f[..])).collect(),
+                    Some(fragments.len()),
+                )
+            }
+            Value::Sequence(_) => return Err("Sequence is not bulk data".into()),
+        };
+        let mut hasher = Sha256::new();
+        for chunk in &chunks {
+            hasher.update(chunk);
+        }
+        Ok(Self {
+            byte_length: chunks.iter().map(|chunk| chunk.len()).sum(),
+            sha256: format!("{:x}", hasher.finalize()),
+            chunks,
+            fragment_count,
+        })
+    }
+
+    pub fn bytes(&self) -> Cow<'_, [u8]> {
+        match &self.chunks[..] {
+            [chunk] => Cow::Borrowed(chunk),
+            chunks => Cow::Owned(chunks.concat()),
+        }
+    }
+
+    // Content-addressed store: the bytes go to <dir>/<first two hex digits>/<sha256>, written
+    // once and moved into place so concurrent workers never see partial files.
+    pub fn store(&self, dir: &Path) -> std::io::Result<()> {
+        let shard = dir.join(&self.sha256[..2]);
+        let path = shard.join(&self.sha256);
+        if path.exists() {
+            return Ok(());
+        }
+        fs::create_dir_all(&shard)?;
+        let mut file = NamedTempFile::new_in(&shard)?;
+        for chunk in &self.chunks {
+            file.write_all(chunk)?;
+        }
+        file.persist(&path).map_err(|e| e.error)?;
+        Ok(())
+    }
+}
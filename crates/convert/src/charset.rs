@@ -0,0 +1,523 @@
+use dicom::core::{PrimitiveValue, Tag, VR};
+use dicom::encoding::text::SpecificCharacterSet;
+use dicom::encoding::transfer_syntax::TransferSyntaxIndex;
+use dicom::object::{FileMetaTable, InMemDicomObject};
+use dicom::parser::dataset::DataToken;
+use dicom::parser::dataset::read::{
+    DataSetReader, DataSetReaderOptions, Error as ReadError, ValueReadStrategy,
+};
+use dicom::transfer_syntax::TransferSyntaxRegistry;
+use encoding::all::{
+    EUC_JP, GB18030, ISO_8859_1, ISO_8859_2, ISO_8859_3, ISO_8859_4, ISO_8859_5, ISO_8859_6,
+    ISO_8859_7, ISO_8859_8, ISO_8859_15, UTF_8, WINDOWS_874, WINDOWS_949, WINDOWS_1254,
+};
+use encoding::{DecoderTrap, EncodingRef};
+use std::collections::BTreeMap;
+use std::error::Error;
+
+const ESC: u8 = 0x1B;
+
+#[derive(Clone, Copy)]
+enum CodeElement {
+    Ascii,
+    JisX0201Romaji,
+    JisX0201Katakana,
+    JisX0208,
+    JisX0212,
+    KsX1001,
+    Gb2312,
+    SingleByte(EncodingRef),
+}
+
+// A text value that could not be decoded without loss, located by its tag path.
+pub struct CharsetError {
+    pub tag: Tag,
+    pub tag_path: String,
+    pub vr: VR,
+    pub lossy: String,
+    pub message: String,
+}
+
+// Undecoded bytes of the text values of a dataset, mirroring its sequence items.
+#[derive(Default)]
+pub struct RawTexts {
+    values: BTreeMap<Tag, (VR, Vec<u8>)>,
+    items: BTreeMap<Tag, Vec<RawTexts>>,
+}
+
+#[derive(Default)]
+pub struct CharacterSet {
+    terms: Vec<String>,
+}
+
+// Initial G0 and G1 code elements designated by a defined term of (0008,0005).
+fn designation(term: &str) -> Option<(CodeElement, Option<CodeElement>)> {
+    use CodeElement::*;
+    let g1 = |encoding: EncodingRef| Some((Ascii, Some(SingleByte(encoding))));
+    match term.trim() {
+        "" | "ISO_IR 6" | "ISO 2022 IR 6" => Some((Ascii, None)),
+        "ISO_IR 100" | "ISO 2022 IR 100" => g1(ISO_8859_1),
+        "ISO_IR 101" | "ISO 2022 IR 101" => g1(ISO_8859_2),
+        "ISO_IR 109" | "ISO 2022 IR 109" => g1(ISO_8859_3),
+        "ISO_IR 110" | "ISO 2022 IR 110" => g1(ISO_8859_4),
+        "ISO_IR 144" | "ISO 2022 IR 144" => g1(ISO_8859_5),
+        "ISO_IR 127" | "ISO 2022 IR 127" => g1(ISO_8859_6),
+        "ISO_IR 126" | "ISO 2022 IR 126" => g1(ISO_8859_7),
+        "ISO_IR 138" | "ISO 2022 IR 138" => g1(ISO_8859_8),
+        "ISO_IR 148" | "ISO 2022 IR 148" => g1(WINDOWS_1254),
+        "ISO_IR 203" | "ISO 2022 IR 203" => g1(ISO_8859_15),
+        "ISO_IR 166" | "ISO 2022 IR 166" => g1(WINDOWS_874),
+        "ISO_IR 13" | "ISO 2022 IR 13" => Some((JisX0201Romaji, Some(JisX0201Katakana))),
+        "ISO_IR 87" | "ISO 2022 IR 87" => Some((JisX0208, None)),
+        "ISO 2022 IR 159" => Some((JisX0212, None)),
+        "ISO_IR 149" | "ISO 2022 IR 149" => Some((Ascii, Some(KsX1001))),
+        "ISO 2022 IR 58" => Some((Ascii, Some(Gb2312))),
+        _ => None,
+    }
+}
+
+// Character sets without code extensions, whose values are decoded as a whole.
+fn unextended_encoding(term: &str) -> Option<EncodingRef> {
+    match term.trim() {
+        "ISO_IR 192" => Some(UTF_8),
+        "GB18030" => Some(GB18030),
+        _ => None,
+    }
+}
+
+// Returns the length of the escape sequence (excluding ESC) and the code element it designates
+// to G0 (false) or G1 (true).
+fn escape_sequence(bytes: &[u8]) -> Option<(usize, bool, CodeElement)> {
+    use CodeElement::*;
+    let single_byte = |encoding: EncodingRef| Some((2, true, SingleByte(encoding)));
+    match bytes {
+        [b'(', b'B', ..] => Some((2, false, Ascii)),
+        [b'(', b'J', ..] => Some((2, false, JisX0201Romaji)),
+        [b')', b'I', ..] => Some((2, true, JisX0201Katakana)),
+        [b'$', b'B', ..] => Some((2, false, JisX0208)),
+        [b'$', b'(', b'D', ..] => Some((3, false, JisX0212)),
+        [b'$', b')', b'C', ..] => Some((3, true, KsX1001)),
+        [b'$', b')', b'A', ..] => Some((3, true, Gb2312)),
+        [b'-', b'A', ..] => single_byte(ISO_8859_1),
+        [b'-', b'B', ..] => single_byte(ISO_8859_2),
+        [b'-', b'C', ..] => single_byte(ISO_8859_3),
+        [b'-', b'D', ..] => single_byte(ISO_8859_4),
+        [b'-', b'L', ..] => single_byte(ISO_8859_5),
+        [b'-', b'G', ..] => single_byte(ISO_8859_6),
+        [b'-', b'F', ..] => single_byte(ISO_8859_7),
+        [b'-', b'H', ..] => single_byte(ISO_8859_8),
+        [b'-', b'M', ..] => single_byte(WINDOWS_1254),
+        [b'-', b'b', ..] => single_byte(ISO_8859_15),
+        [b'-', b'T', ..] => single_byte(WINDOWS_874),
+        _ => None,
+    }
+}
+
+fn decode_with(encoding: EncodingRef, bytes: &[u8]) -> Option<String> {
+    encoding.decode(bytes, DecoderTrap::Strict).ok()
+}
+
+// A backslash may be the second byte of a GB18030 character, so values are split after decoding.
+fn decode_unextended(
+    encoding: EncodingRef,
+    bytes: &[u8],
+    multi_valued: bool,
+) -> (Vec<String>, usize) {
+    let (text, invalid) = match decode_with(encoding, bytes) {
+        Some(text) => (text, 0),
+        None => {
+            let text = encoding
+                .decode(bytes, DecoderTrap::Replace)
+                .unwrap_or_default();
+            let invalid = text.matches(char::REPLACEMENT_CHARACTER).count().max(1);
+            (text, invalid)
+        }
+    };
+    let values = if multi_valued {
+        text.split('\\').map(String::from).collect()
+    } else {
+        vec![text]
+    };
+    (values, invalid)
+}
+
+// Value representations whose values are affected by the Specific Character Set.
+fn is_text_vr(vr: VR) -> bool {
+    matches!(
+        vr,
+        VR::SH | VR::LO | VR::ST | VR::LT | VR::UT | VR::PN | VR::UC
+    )
+}
+
+fn is_multi_valued(vr: VR) -> bool {
+    !matches!(vr, VR::ST | VR::LT | VR::UT)
+}
+
+impl RawTexts {
+    // Parses the file a second time, keeping text values as bytes.
+    pub fn read(file_bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let mut reader = match file_bytes.get(128..132) {
+            Some(b"DICM") => &file_bytes[128..],
+            _ => file_bytes,
+        };
+        let meta = FileMetaTable::from_reader(&mut reader)?;
+        let transfer_syntax = TransferSyntaxRegistry
+            .get(meta.transfer_syntax())
+            .ok_or_else(|| format!("Unknown transfer syntax '{}'", meta.transfer_syntax()))?;
+        let options = DataSetReaderOptions::default().value_read(ValueReadStrategy::Raw);
+        let mut tokens = DataSetReader::new_with_ts_options(reader, transfer_syntax, options)?;
+        Self::read_dataset(&mut tokens)
+    }
+
+    fn read_dataset(
+        tokens: &mut impl Iterator<Item = Result<DataToken, ReadError>>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut raw = Self::default();
+        let mut header = None;
+        while let Some(token) = tokens.next() {
+            match token? {
+                DataToken::ElementHeader(h) => header = Some(h),
+                DataToken::PrimitiveValue(value) => {
+                    if let Some(h) = header.take().filter(|h| is_text_vr(h.vr())) {
+                        raw.values
+                            .insert(h.tag, (h.vr(), value.to_bytes().into_owned()));
+                    }
+                }
+                DataToken::SequenceStart { tag, .. } => {
+                    let items = Self::read_sequence(tokens)?;
+                    raw.items.insert(tag, items);
+                }
+                DataToken::PixelSequenceStart => {
+                    for token in tokens.by_ref() {
+                        if matches!(token?, DataToken::SequenceEnd) {
+                            break;
+                        }
+                    }
+                }
+                DataToken::ItemEnd => break,
+                _ => {}
+            }
+        }
+        Ok(raw)
+    }
+
+    fn read_sequence(
+        tokens: &mut impl Iterator<Item = Result<DataToken, ReadError>>,
+    ) -> Result<Vec<Self>, Box<dyn Error>> {
+        let mut items = Vec::new();
+        while let Some(token) = tokens.next() {
+            match token? {
+                DataToken::ItemStart { .. } => items.push(Self::read_dataset(tokens)?),
+                DataToken::SequenceEnd => break,
+                _ => {}
+            }
+        }
+        Ok(items)
+    }
+}
+
+impl CharacterSet {
+    pub fn from_dataset(dicom_object: &InMemDicomObject) -> Self {
+        let terms = dicom_object
+            .element(Tag(0x0008, 0x0005))
+            .ok()
+            .and_then(|e| e.strings().ok())
+            .map(|terms| terms.iter().map(|t| t.trim().to_string()).collect())
+            .unwrap_or_default();
+        Self { terms }
+    }
+
+    pub fn unsupported_terms(&self) -> impl Iterator<Item = &str> {
+        self.terms.iter().map(|t| t.as_str()).filter(|t| {
+            designation(t).is_none()
+                && unextended_encoding(t).is_none()
+                && SpecificCharacterSet::from_code(t).is_none()
+        })
+    }
+
+    fn first_term(&self) -> &str {
+        self.terms.first().map_or("", |t| t.as_str())
+    }
+
+    // Replaces the text values of the dataset with the values decoded from their raw bytes. The
+    // DICOM parser decodes them with the first term of (0008,0005) only and escapes invalid
+    // bytes without failing.
+    pub fn redecode(
+        &self,
+        dicom_object: &mut InMemDicomObject,
+        raw: &RawTexts,
+        tag_path: &str,
+        errors: &mut Vec<CharsetError>,
+    ) {
+        let child_tag_path = |tag: Tag| {
+            let tag_hex = format!("{:04X}{:04X}", tag.group(), tag.element());
+            if tag_path.is_empty() {
+                tag_hex
+            } else {
+                format!("{}/{}", tag_path, tag_hex)
+            }
+        };
+        for (&tag, (vr, bytes)) in &raw.values {
+            let (values, invalid) = self.decode_bytes(bytes, is_multi_valued(*vr));
+            if invalid > 0 {
+                errors.push(CharsetError {
+                    tag,
+                    tag_path: child_tag_path(tag),
+                    vr: *vr,
+                    lossy: values.join("\\"),
+                    message: format!(
+                        "{} invalid byte sequence(s) for character set '{}'",
+                        invalid,
+                        self.terms.join("\\")
+                    ),
+                });
+            }
+            let value = if is_multi_valued(*vr) {
+                PrimitiveValue::Strs(values.into())
+            } else {
+                PrimitiveValue::Str(values.concat())
+            };
+            dicom_object.update_value(tag, |v| *v = value.clone().into());
+        }
+        for (&tag, raw_items) in &raw.items {
+            let item_tag_path = child_tag_path(tag);
+            dicom_object.update_value(tag, |v| {
+                for (item, raw_item) in v.items_mut().into_iter().flatten().zip(raw_items) {
+                    self.redecode(item, raw_item, &item_tag_path, errors);
+                }
+            });
+        }
+    }
+
+    // Decodes the values of an element and counts the byte sequences that could not be decoded.
+    // A backslash only delimits values while G0 holds a single-byte code element, since it may
+    // be the second byte of a multi-byte character.
+    fn decode_bytes(&self, bytes: &[u8], multi_valued: bool) -> (Vec<String>, usize) {
+        if let Some(encoding) = unextended_encoding(self.first_term()) {
+            return decode_unextended(encoding, bytes, multi_valued);
+        }
+        let (initial_g0, initial_g1) =
+            designation(self.first_term()).unwrap_or((CodeElement::Ascii, None));
+        let (mut g0, mut g1) = (initial_g0, initial_g1);
+        let mut values = Vec::new();
+        let mut out = String::with_capacity(bytes.len());
+        let mut invalid = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            let b = bytes[i];
+            if b == b'\\'
+                && multi_valued
+                && !matches!(g0, CodeElement::JisX0208 | CodeElement::JisX0212)
+            {
+                values.push(std::mem::take(&mut out));
+                (g0, g1) = (initial_g0, initial_g1);
+                i += 1;
+                continue;
+            }
+            if b == ESC {
+                match escape_sequence(&bytes[i + 1..]) {
+                    Some((len, true, element)) => {
+                        g1 = Some(element);
+                        i += 1 + len;
+                    }
+                    Some((len, false, element)) => {
+                        g0 = element;
+                        i += 1 + len;
+                    }
+                    None => {
+                        invalid += 1;
+                        out.push(char::REPLACEMENT_CHARACTER);
+                        i += 1;
+                    }
+                }
+                continue;
+            }
+            if b < 0x20 {
+                out.push(b as char);
+                if matches!(b, b'\r' | b'\n' | b'\t' | 0x0C) {
+                    (g0, g1) = (initial_g0, initial_g1);
+                }
+                i += 1;
+                continue;
+            }
+            let element = if b < 0x80 { Some(g0) } else { g1 };
+            let pair = bytes.get(i..i + 2);
+            let (decoded, len) = match element {
+                Some(CodeElement::Ascii) => (Some(String::from(b as char)), 1),
+                Some(CodeElement::JisX0201Romaji) => (
+                    Some(String::from(match b {
+                        0x5C => '¥',
+                        0x7E => '‾',
+                        _ => b as char,
+                    })),
+                    1,
+                ),
+                Some(CodeElement::JisX0201Katakana) => (
+                    (0xA1..=0xDF)
+                        .contains(&b)
+                        .then(|| char::from_u32(0xFF61 + (b - 0xA1) as u32))
+                        .flatten()
+                        .map(String::from),
+                    1,
+                ),
+                Some(CodeElement::JisX0208) if b == b' ' => (Some(String::from(' ')), 1),
+                Some(CodeElement::JisX0208) => (
+                    pair.and_then(|p| decode_with(EUC_JP, &[p[0] | 0x80, p[1] | 0x80])),
+                    2,
+                ),
+                Some(CodeElement::JisX0212) if b == b' ' => (Some(String::from(' ')), 1),
+                Some(CodeElement::JisX0212) => (
+                    pair.and_then(|p| decode_with(EUC_JP, &[0x8F, p[0] | 0x80, p[1] | 0x80])),
+                    2,
+                ),
+                Some(CodeElement::KsX1001) => (pair.and_then(|p| decode_with(WINDOWS_949, p)), 2),
+                Some(CodeElement::Gb2312) => (pair.and_then(|p| decode_with(GB18030, p)), 2),
+                Some(CodeElement::SingleByte(encoding)) => (decode_with(encoding, &[b]), 1),
+                None => (None, 1),
+            };
+            match decoded {
+                Some(s) => out.push_str(&s),
+                None => {
+                    invalid += 1;
+                    out.push(char::REPLACEMENT_CHARACTER);
+                }
+            }
+            i += len;
+        }
+        values.push(out);
+        (values, invalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom::object::meta::FileMetaTableBuilder;
+    use dicom::object::open_file;
+    use std::io::Write;
+
+    // PS3.5 Annex H.3.1, "Yamada^Tarou=山田^太郎=やまだ^たろう".
+    const JAPANESE: &[u8] = b"Yamada^Tarou=\x1b$B;3ED\x1b(B^\x1b$BB@O:\x1b(B=\
+        \x1b$B$d$^$@\x1b(B^\x1b$B$?$m$&\x1b(B";
+    // PS3.5 Annex I.2, "Hong^Gildong=洪^吉洞=홍^길동".
+    const KOREAN: &[u8] = b"Hong^Gildong=\x1b$)C\xfb\xf3^\x1b$)C\xd1\xce\xd4\xd7=\
+        \x1b$)C\xc8\xab^\x1b$)C\xb1\xe6\xb5\xbf";
+
+    fn charset(terms: &[&str]) -> CharacterSet {
+        CharacterSet {
+            terms: terms.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn decodes_iso_2022_ir_87() {
+        let (values, invalid) = charset(&["", "ISO 2022 IR 87"]).decode_bytes(JAPANESE, true);
+        assert_eq!(values, ["Yamada^Tarou=山田^太郎=やまだ^たろう"]);
+        assert_eq!(invalid, 0);
+    }
+
+    #[test]
+    fn decodes_iso_2022_ir_149() {
+        let (values, invalid) = charset(&["", "ISO 2022 IR 149"]).decode_bytes(KOREAN, true);
+        assert_eq!(values, ["Hong^Gildong=洪^吉洞=홍^길동"]);
+        assert_eq!(invalid, 0);
+    }
+
+    #[test]
+    fn splits_values_only_outside_multi_byte_code_elements() {
+        // 旬 is encoded as 0x3D 0x5C in JIS X 0208.
+        let bytes = b"\x1b$B=\\\x1b(B\\A";
+        let charset = charset(&["", "ISO 2022 IR 87"]);
+        assert_eq!(charset.decode_bytes(bytes, true).0, ["旬", "A"]);
+        assert_eq!(charset.decode_bytes(bytes, false).0, ["旬\\A"]);
+    }
+
+    #[test]
+    fn counts_invalid_byte_sequences() {
+        let (values, invalid) =
+            charset(&["", "ISO 2022 IR 149"]).decode_bytes(b"\x1b$)C\xc8", true);
+        assert_eq!(values, ["\u{FFFD}"]);
+        assert_eq!(invalid, 1);
+    }
+
+    #[test]
+    fn counts_invalid_bytes_of_unextended_character_sets() {
+        let utf8 = charset(&["ISO_IR 192"]);
+        assert_eq!(
+            utf8.decode_bytes("Müller\\Zoë".as_bytes(), true),
+            (vec!["Müller".to_string(), "Zoë".to_string()], 0)
+        );
+        let (values, invalid) = utf8.decode_bytes(b"M\xfcller", true);
+        assert_eq!(values, ["M\u{FFFD}ller"]);
+        assert_eq!(invalid, 1);
+        // 乗 is encoded as 0x81 0x5C in GB18030.
+        let (values, invalid) = charset(&["GB18030"]).decode_bytes(b"\x81\\\\A", true);
+        assert_eq!(values, ["乗", "A"]);
+        assert_eq!(invalid, 0);
+    }
+
+    #[test]
+    fn counts_invalid_bytes_of_the_default_repertoire() {
+        let (values, invalid) = charset(&[]).decode_bytes(b"M\xfcller", true);
+        assert_eq!(values, ["M\u{FFFD}ller"]);
+        assert_eq!(invalid, 1);
+    }
+
+    // Explicit VR little endian element with a 16 bit length.
+    fn element(group: u16, element: u16, vr: &[u8; 2], value: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend(group.to_le_bytes());
+        bytes.extend(element.to_le_bytes());
+        bytes.extend(vr);
+        bytes.extend((value.len() as u16).to_le_bytes());
+        bytes.extend(value);
+        bytes
+    }
+
+    #[test]
+    fn redecodes_dataset_from_raw_bytes() {
+        let meta = FileMetaTableBuilder::new()
+            .media_storage_sop_class_uid("1.2.840.10008.5.1.4.1.1.88.33")
+            .media_storage_sop_instance_uid("1.2.3")
+            .transfer_syntax("1.2.840.10008.1.2.1")
+            .build()
+            .unwrap();
+        let mut bytes = vec![0; 128];
+        bytes.extend(b"DICM");
+        meta.write(&mut bytes).unwrap();
+        bytes.extend(element(0x0008, 0x0005, b"CS", b"\\ISO 2022 IR 87 "));
+        let mut name = JAPANESE.to_vec();
+        name.push(b' ');
+        bytes.extend(element(0x0010, 0x0010, b"PN", &name));
+        // Content Sequence with one item of undefined length.
+        bytes.extend(b"\x40\x00\x30\xa7SQ\x00\x00\xff\xff\xff\xff");
+        bytes.extend(b"\xfe\xff\x00\xe0\xff\xff\xff\xff");
+        bytes.extend(element(0x0040, 0xA160, b"LT", b"\x1b$B=\\\x1b(B\xff"));
+        bytes.extend(b"\xfe\xff\x0d\xe0\x00\x00\x00\x00");
+        bytes.extend(b"\xfe\xff\xdd\xe0\x00\x00\x00\x00");
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&bytes).unwrap();
+
+        let mut dicom_object = open_file(file.path()).unwrap().into_inner();
+        let charset = CharacterSet::from_dataset(&dicom_object);
+        let mut errors = Vec::new();
+        let raw = RawTexts::read(&bytes).unwrap();
+        charset.redecode(&mut dicom_object, &raw, "", &mut errors);
+
+        let name = dicom_object.element(Tag(0x0010, 0x0010)).unwrap();
+        assert_eq!(
+            name.string().unwrap().trim(),
+            "Yamada^Tarou=山田^太郎=やまだ^たろう"
+        );
+        let item = &dicom_object
+            .element(Tag(0x0040, 0xA730))
+            .unwrap()
+            .items()
+            .unwrap()[0];
+        let text = item.element(Tag(0x0040, 0xA160)).unwrap();
+        assert_eq!(text.string().unwrap(), "旬\u{FFFD}");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].tag_path, "0040A730/0040A160");
+        assert_eq!(errors[0].vr, VR::LT);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::turtle;
+use dicom::core::chrono::{Datelike, Duration, NaiveDate};
 use dicom::core::value::{DicomDate, DicomDateTime, DicomTime};
 
 pub fn age_string_to_years(age_str: &str) -> Result<f64, String> {
@@ -66,3 +67,101 @@ pub fn datetime_to_iso(ddt: &DicomDateTime) -> String {
             .map_or(String::new(), |tz| format!("{}", tz))
     )
 }
+
+// Dates are shifted by whole days, so times of day and intervals stay intact. Partial dates are
+// shifted from their first day and keep their precision.
+pub fn shift_date(dd: &DicomDate, days: i64) -> Result<DicomDate, String> {
+    let year = *dd.year() as i32;
+    let month = dd.month().copied().unwrap_or(1) as u32;
+    let day = dd.day().copied().unwrap_or(1) as u32;
+    let shifted = NaiveDate::from_ymd_opt(year, month, day)
+        .and_then(|date| date.checked_add_signed(Duration::days(days)))
+        .ok_or_else(|| format!("Cannot shift date '{}'", date_to_iso(dd)))?;
+    let year = u16::try_from(shifted.year()).map_err(|e| e.to_string())?;
+    match (dd.month(), dd.day()) {
+        (None, _) => DicomDate::from_y(year),
+        (Some(_), None) => DicomDate::from_ym(year, shifted.month() as u8),
+        (Some(_), Some(_)) => DicomDate::from_ymd(year, shifted.month() as u8, shifted.day() as u8),
+    }
+    .map_err(|e| e.to_string())
+}
+
+pub fn shift_datetime(ddt: &DicomDateTime, days: i64) -> Result<DicomDateTime, String> {
+    let date = shift_date(ddt.date(), days)?;
+    match (ddt.time(), ddt.time_zone()) {
+        (Some(time), Some(time_zone)) => {
+            DicomDateTime::from_date_and_time_with_time_zone(date, *time, *time_zone)
+        }
+        (Some(time), None) => DicomDateTime::from_date_and_time(date, *time),
+        (None, Some(time_zone)) => Ok(DicomDateTime::from_date_with_time_zone(date, *time_zone)),
+        (None, None) => Ok(DicomDateTime::from_date(date)),
+    }
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom::core::chrono::FixedOffset;
+
+    fn shifted_date(dd: DicomDate, days: i64) -> String {
+        date_to_iso(&shift_date(&dd, days).unwrap())
+    }
+
+    #[test]
+    fn full_dates_are_shifted_by_days() {
+        assert_eq!(
+            shifted_date(DicomDate::from_ymd(2024, 2, 28).unwrap(), 1),
+            "2024-02-29"
+        );
+        assert_eq!(
+            shifted_date(DicomDate::from_ymd(2024, 12, 31).unwrap(), 1),
+            "2025-01-01"
+        );
+        assert_eq!(
+            shifted_date(DicomDate::from_ymd(2024, 1, 1).unwrap(), -1),
+            "2023-12-31"
+        );
+    }
+
+    #[test]
+    fn partial_dates_keep_their_precision() {
+        assert_eq!(
+            shifted_date(DicomDate::from_ym(2024, 12).unwrap(), 30),
+            "2024-12"
+        );
+        assert_eq!(
+            shifted_date(DicomDate::from_ym(2024, 12).unwrap(), 31),
+            "2025-01"
+        );
+        assert_eq!(
+            shifted_date(DicomDate::from_ym(2024, 3).unwrap(), -1),
+            "2024-02"
+        );
+        assert_eq!(shifted_date(DicomDate::from_y(2024).unwrap(), 365), "2024");
+        assert_eq!(shifted_date(DicomDate::from_y(2024).unwrap(), 366), "2025");
+        assert_eq!(shifted_date(DicomDate::from_y(2024).unwrap(), -1), "2023");
+    }
+
+    #[test]
+    fn dates_out_of_range_are_errors() {
+        assert!(shift_date(&DicomDate::from_y(0).unwrap(), -1).is_err());
+    }
+
+    #[test]
+    fn datetimes_keep_their_time_and_time_zone() {
+        let date = DicomDate::from_ymd(2024, 12, 31).unwrap();
+        let time = DicomTime::from_hms(23, 59, 30).unwrap();
+        let time_zone = FixedOffset::east_opt(3600).unwrap();
+        let ddt = DicomDateTime::from_date_and_time_with_time_zone(date, time, time_zone).unwrap();
+        assert_eq!(
+            datetime_to_iso(&shift_datetime(&ddt, 1).unwrap()),
+            "2025-01-01T23:59:30+01:00"
+        );
+        let ddt = DicomDateTime::from_date(DicomDate::from_ym(2024, 12).unwrap());
+        assert_eq!(
+            datetime_to_iso(&shift_datetime(&ddt, 31).unwrap()),
+            "2025-01"
+        );
+    }
+}
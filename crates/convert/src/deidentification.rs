@@ -0,0 +1,72 @@
+use config::Config;
+use config::deidentification::ProfileAction;
+use dicom::core::Tag;
+use dicom::core::dictionary::DataDictionary;
+use dicom::dictionary_std::StandardDataDictionary;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+// Replacement for action U: the keyed UID of the pseudonymization, so references between
+// documents stay intact. Configurations replacing UIDs always have a key.
+pub fn replacement_uid(config: &Config, uid: &str) -> Option<String> {
+    config
+        .pseudonymization
+        .as_ref()
+        .map(|pseudonymization| pseudonymization.uid(uid))
+}
+
+#[derive(Default)]
+pub struct Report {
+    counts: BTreeMap<(Tag, ProfileAction), usize>,
+    pub scrubbed: BTreeMap<String, usize>,
+}
+
+impl Report {
+    pub fn record(&mut self, tag: Tag, action: ProfileAction) {
+        *self.counts.entry((tag, action)).or_default() += 1;
+    }
+
+    pub fn merge(&mut self, other: Report) {
+        for (key, count) in other.counts {
+            *self.counts.entry(key).or_default() += count;
+        }
+        for (label, count) in other.scrubbed {
+            *self.scrubbed.entry(label).or_default() += count;
+        }
+    }
+
+    pub fn totals(&self) -> BTreeMap<ProfileAction, usize> {
+        let mut totals = BTreeMap::new();
+        for (&(_, action), count) in &self.counts {
+            *totals.entry(action).or_default() += count;
+        }
+        totals
+    }
+
+    pub fn write_tsv(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writeln!(writer, "tag\tkeyword\taction\tcount")?;
+        for (&(tag, action), count) in &self.counts {
+            let keyword = StandardDataDictionary
+                .by_tag(tag)
+                .map_or("", |entry| entry.alias);
+            writeln!(
+                writer,
+                "({:04X},{:04X})\t{}\t{}\t{}",
+                tag.group(),
+                tag.element(),
+                keyword,
+                action,
+                count
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn write_scrubbing_tsv(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writeln!(writer, "label\tcount")?;
+        for (label, count) in &self.scrubbed {
+            writeln!(writer, "{}\t{}", label, count)?;
+        }
+        Ok(())
+    }
+}
@@ -1,22 +1,37 @@
+use crate::bulk::BulkData;
 use crate::datetime::{
-    age_string_to_years, date_to_iso, datetime_to_iso, iso_string_to_typed_literal, time_to_iso,
+    age_string_to_years, date_to_iso, datetime_to_iso, iso_string_to_typed_literal, shift_date,
+    shift_datetime, time_to_iso,
 };
-use crate::turtle;
+use crate::deidentification::{Report, replacement_uid};
+use crate::encapsulated::{self, DocumentKind};
+use crate::errors::{
+    CategorizedError, ConversionError, ErrorCategory, ErrorWriter, truncated_value,
+    value_error_category,
+};
+use crate::geometry;
+use crate::numeric::{decimal_string_to_typed_literal, float_to_typed_literal};
+use crate::scrub::Scrubber;
+use crate::turtle::{self, TripleSink};
 use config::Config;
-use dicom::core::header::{HasLength, Header};
+use config::deidentification::ProfileAction;
+use config::redaction::{self, ContentItem, RedactionAction};
+use dicom::core::header::HasLength;
+use dicom::core::value::PersonName;
 use dicom::core::{Tag, VR};
 use dicom::object::InMemDicomObject;
-use log::debug;
+use dicom::object::mem::InMemElement;
 use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::error::Error;
 use std::io::Write;
+use std::path::Path;
 use std::sync::LazyLock;
 
 static INDEX_IRI: LazyLock<turtle::IRI> =
     LazyLock::new(|| turtle::IRI::prefix("dicom2rdf", "index"));
 static ITEM_IRI: LazyLock<turtle::IRI> = LazyLock::new(|| turtle::IRI::prefix("dicom2rdf", "item"));
-static PERSON_NAME_IRI: LazyLock<turtle::IRI> =
-    LazyLock::new(|| turtle::IRI::prefix("dicom2rdf", "person_name"));
 static PN_FAMILY_IRI: LazyLock<turtle::IRI> =
     LazyLock::new(|| turtle::IRI::prefix("dicom2rdf", "pn_family"));
 static PN_MIDDLE_IRI: LazyLock<turtle::IRI> =
@@ -27,88 +42,793 @@ static PN_PREFIX_IRI: LazyLock<turtle::IRI> =
     LazyLock::new(|| turtle::IRI::prefix("dicom2rdf", "pn_prefix"));
 static PN_SUFFIX_IRI: LazyLock<turtle::IRI> =
     LazyLock::new(|| turtle::IRI::prefix("dicom2rdf", "pn_suffix"));
+static RDF_TYPE_IRI: LazyLock<turtle::IRI> = LazyLock::new(|| turtle::IRI::prefix("rdf", "type"));
+static BULK_DATA_IRI: LazyLock<turtle::IRI> =
+    LazyLock::new(|| turtle::IRI::prefix("dicom2rdf", "BulkData"));
+static BYTE_LENGTH_IRI: LazyLock<turtle::IRI> =
+    LazyLock::new(|| turtle::IRI::prefix("dicom2rdf", "byte_length"));
+static SHA256_IRI: LazyLock<turtle::IRI> =
+    LazyLock::new(|| turtle::IRI::prefix("dicom2rdf", "sha256"));
+static TRANSFER_SYNTAX_IRI: LazyLock<turtle::IRI> =
+    LazyLock::new(|| turtle::IRI::prefix("dicom2rdf", "transfer_syntax"));
+static FRAGMENT_COUNT_IRI: LazyLock<turtle::IRI> =
+    LazyLock::new(|| turtle::IRI::prefix("dicom2rdf", "fragment_count"));
+static TEXT_IRI: LazyLock<turtle::IRI> = LazyLock::new(|| turtle::IRI::prefix("dicom2rdf", "text"));
+static HAS_GEOMETRY_IRI: LazyLock<turtle::IRI> =
+    LazyLock::new(|| turtle::IRI::prefix("geo", "hasGeometry"));
+static AS_WKT_IRI: LazyLock<turtle::IRI> = LazyLock::new(|| turtle::IRI::prefix("geo", "asWKT"));
+static TIME_OFFSET_IRI: LazyLock<turtle::IRI> =
+    LazyLock::new(|| turtle::IRI::prefix("dicom2rdf", "time_offset"));
+static REFERENCED_CONTENT_ITEM_IRI: LazyLock<turtle::IRI> =
+    LazyLock::new(|| turtle::IRI::prefix("dicom2rdf", "referenced_content_item"));
+static PIXEL_DATA_ATTRIBUTES: LazyLock<[(Tag, turtle::IRI); 3]> = LazyLock::new(|| {
+    [
+        (
+            Tag(0x0028, 0x0010),
+            turtle::IRI::prefix("dicom2rdf", "rows"),
+        ),
+        (
+            Tag(0x0028, 0x0011),
+            turtle::IRI::prefix("dicom2rdf", "columns"),
+        ),
+        (
+            Tag(0x0028, 0x0008),
+            turtle::IRI::prefix("dicom2rdf", "number_of_frames"),
+        ),
+    ]
+});
+static PN_GROUPS: LazyLock<[(&str, turtle::IRI); 3]> = LazyLock::new(|| {
+    [
+        (
+            "alphabetic",
+            turtle::IRI::prefix("dicom2rdf", "pn_alphabetic"),
+        ),
+        (
+            "ideographic",
+            turtle::IRI::prefix("dicom2rdf", "pn_ideographic"),
+        ),
+        ("phonetic", turtle::IRI::prefix("dicom2rdf", "pn_phonetic")),
+    ]
+});
 
-pub fn write_triples(
-    triple_writer: &mut impl Write,
-    error_writer: &mut impl Write,
+// All dates of a patient are shifted by the same number of days. Without a Patient ID there is
+// no shift to share, so dates are removed instead.
+#[derive(Clone, Copy)]
+pub enum DateShift {
+    Days(i64),
+    Remove,
+}
+
+pub struct Document<'a> {
+    // Identifies the document in error records.
+    pub file_path: &'a str,
+    pub config: &'a Config,
+    pub namespace: &'a str,
+    pub report: RefCell<Report>,
+    pub date_shift: Option<DateShift>,
+    pub scrubber: Option<Scrubber<'a>>,
+    pub transfer_syntax: &'a str,
+    pub bulk_data_dir: Option<&'a Path>,
+    pub dataset: &'a InMemDicomObject,
+    pub root: &'a Node<'a>,
+}
+
+pub struct Node<'a> {
+    pub subject: &'a turtle::IRI,
+    pub path: &'a str,
+    pub tag_path: &'a str,
+    pub depth: u8,
+    pub language: Option<&'a str>,
+    pub template: Option<&'a str>,
+    pub redaction: Option<Redaction<'a>>,
+}
+
+// A redaction applying to every value below a data element or content item. Redacted values
+// are replaced by "<label>".
+#[derive(Clone, Copy)]
+pub struct Redaction<'a> {
+    pub action: RedactionAction,
+    pub label: &'a str,
+}
+
+const CONTENT_SEQUENCE: Tag = Tag(0x0040, 0xA730);
+const REFERENCED_CONTENT_ITEM_IDENTIFIER: Tag = Tag(0x0040, 0xDB73);
+
+// Data elements holding the value of a content item.
+const CONTENT_ITEM_VALUE_TAGS: [Tag; 18] = [
+    Tag(0x0008, 0x1199),
+    Tag(0x0040, 0xA120),
+    Tag(0x0040, 0xA121),
+    Tag(0x0040, 0xA122),
+    Tag(0x0040, 0xA123),
+    Tag(0x0040, 0xA124),
+    Tag(0x0040, 0xA130),
+    Tag(0x0040, 0xA132),
+    Tag(0x0040, 0xA138),
+    Tag(0x0040, 0xA13A),
+    Tag(0x0040, 0xA160),
+    Tag(0x0040, 0xA162),
+    Tag(0x0040, 0xA163),
+    Tag(0x0040, 0xA168),
+    Tag(0x0040, 0xA300),
+    Tag(0x0040, 0xA301),
+    Tag(0x0070, 0x0022),
+    Tag(0x0070, 0x0023),
+];
+
+// Binary values are not worth logging.
+fn raw_value(data_element: &InMemElement) -> Option<String> {
+    match data_element.vr() {
+        VR::OB | VR::OD | VR::OF | VR::OL | VR::OV | VR::OW | VR::SQ | VR::UN => None,
+        _ => data_element
+            .value()
+            .to_str()
+            .ok()
+            .map(|value| truncated_value(&value)),
+    }
+}
+
+fn log_error(
+    error_writer: &mut ErrorWriter<impl Write>,
+    document: &Document,
+    tag_path: &str,
+    data_element: &InMemElement,
+    category: ErrorCategory,
+    e: impl std::fmt::Display,
+) -> std::io::Result<()> {
+    error_writer.log(&ConversionError {
+        category,
+        file: document.file_path,
+        tag: Some(data_element.header().tag),
+        tag_path: Some(tag_path),
+        vr: Some(data_element.vr().to_string()),
+        value: raw_value(data_element),
+        message: e.to_string(),
+    })
+}
+
+fn text_values(data_element: &InMemElement) -> Result<Vec<String>, Box<dyn Error>> {
+    Ok(data_element
+        .value()
+        .strings()?
+        .iter()
+        .map(|val| val.trim().trim_end_matches('\0').to_string())
+        .collect())
+}
+
+fn replaced_values(
+    data_element: &InMemElement,
+    replacement: impl Fn(&str) -> Option<String>,
+) -> Result<Vec<String>, String> {
+    data_element
+        .value()
+        .strings()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|value| replacement(value))
+        .collect::<Option<Vec<String>>>()
+        .ok_or_else(|| "No replacement for value".to_string())
+}
+
+fn scrub_text(document: &Document, s: String) -> String {
+    match &document.scrubber {
+        Some(scrubber) => scrubber.scrub(&s, &mut document.report.borrow_mut().scrubbed),
+        None => s,
+    }
+}
+
+fn text_literal(s: String, language: Option<&str>) -> turtle::PlainLiteral {
+    match language {
+        Some(language) => turtle::PlainLiteral::LangString(s, language.to_string()),
+        None => turtle::PlainLiteral::String(s),
+    }
+}
+
+fn first_item(dicom_object: &InMemDicomObject, tag: Tag) -> Option<&InMemDicomObject> {
+    dicom_object.element(tag).ok()?.items()?.first()
+}
+
+fn string_value(dicom_object: &InMemDicomObject, tag: Tag) -> Option<&str> {
+    dicom_object
+        .element(tag)
+        .ok()?
+        .string()
+        .ok()
+        .map(|s| s.trim().trim_end_matches('\0'))
+}
+
+fn is_language_tag(s: &&str) -> bool {
+    let mut subtags = s.split('-');
+    subtags
+        .next()
+        .is_some_and(|t| (1..=8).contains(&t.len()) && t.chars().all(|c| c.is_ascii_alphabetic()))
+        && subtags
+            .all(|t| (1..=8).contains(&t.len()) && t.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+// Language declared on a dataset, either by a "Language of Content Item and Descendants"
+// concept modifier (TID 1204) or by a Language Code Sequence.
+fn declared_language(dicom_object: &InMemDicomObject) -> Option<&str> {
+    let from_content_item = dicom_object
+        .element(Tag(0x0040, 0xA730))
+        .ok()
+        .and_then(|e| e.items())
+        .and_then(|items| {
+            items.iter().find(|item| {
+                first_item(item, Tag(0x0040, 0xA043)).is_some_and(|concept_name| {
+                    string_value(concept_name, Tag(0x0008, 0x0100)) == Some("121049")
+                        && string_value(concept_name, Tag(0x0008, 0x0102)) == Some("DCM")
+                })
+            })
+        })
+        .and_then(|item| first_item(item, Tag(0x0040, 0xA168)));
+    let from_sequence = [Tag(0x0008, 0x0006), Tag(0x0040, 0xA744)]
+        .into_iter()
+        .find_map(|tag| first_item(dicom_object, tag));
+    from_content_item
+        .or(from_sequence)
+        .and_then(|code| string_value(code, Tag(0x0008, 0x0100)))
+        .filter(is_language_tag)
+}
+
+fn declared_template(dicom_object: &InMemDicomObject) -> Option<&str> {
+    first_item(dicom_object, Tag(0x0040, 0xA504))
+        .and_then(|template| string_value(template, Tag(0x0040, 0xDB00)))
+}
+
+fn content_item_redaction<'a>(
+    config: &Config,
+    dicom_object: &'a InMemDicomObject,
+    template: Option<&'a str>,
+) -> Option<Redaction<'a>> {
+    let concept_name = first_item(dicom_object, Tag(0x0040, 0xA043));
+    let code = |tag| concept_name.and_then(|c| string_value(c, tag));
+    let code_meaning = code(Tag(0x0008, 0x0104));
+    let action = redaction::content_item_action(
+        &config.redaction_rules,
+        &ContentItem {
+            code_value: code(Tag(0x0008, 0x0100)),
+            coding_scheme: code(Tag(0x0008, 0x0102)),
+            code_meaning,
+            template,
+        },
+    )?;
+    Some(Redaction {
+        action,
+        label: code_meaning.unwrap_or("redacted"),
+    })
+}
+
+fn redacted_values(
+    document: &Document,
+    data_element: &InMemElement,
+    redaction: Redaction,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let values = if data_element.value().strings().is_ok() {
+        text_values(data_element)?
+    } else {
+        data_element.value().to_multi_str()?.to_vec()
+    };
+    Ok(match redaction.action {
+        RedactionAction::Redact | RedactionAction::DropSubtree => {
+            vec![format!("<{}>", redaction.label)]
+        }
+        RedactionAction::Hash => {
+            let pseudonymization = document
+                .config
+                .pseudonymization
+                .as_ref()
+                .ok_or("Redaction action 'hash' requires a pseudonymization key")?;
+            values.iter().map(|v| pseudonymization.hash(v)).collect()
+        }
+        RedactionAction::Truncate(max_len) => values
+            .into_iter()
+            .map(|v| v.chars().take(max_len).collect())
+            .collect(),
+    })
+}
+
+// Private creators reserving element blocks (gggg,xx00-xxFF) via (gggg,00xx).
+fn private_creators(dicom_object: &InMemDicomObject) -> HashMap<(u16, u16), String> {
+    dicom_object
+        .iter()
+        .filter_map(|data_element| {
+            let tag = data_element.header().tag;
+            if tag.group().is_multiple_of(2) || !(0x0010..=0x00FF).contains(&tag.element()) {
+                return None;
+            }
+            let creator = data_element.string().ok()?.trim().trim_end_matches('\0');
+            Some(((tag.group(), tag.element()), creator.to_string()))
+        })
+        .collect()
+}
+
+fn is_private_attribute(tag: Tag) -> bool {
+    !tag.group().is_multiple_of(2) && tag.element() >= 0x1000
+}
+
+fn private_creator(creators: &HashMap<(u16, u16), String>, tag: Tag) -> Option<&str> {
+    if !is_private_attribute(tag) {
+        return None;
+    }
+    creators
+        .get(&(tag.group(), tag.element() >> 8))
+        .map(|c| c.as_str())
+}
+
+// Bulk data is described by a node with its size and digest instead of its value. Pixel data
+// also links the transfer syntax and the image dimensions of its dataset.
+fn write_bulk_data(
+    triple_writer: &mut impl TripleSink,
     subject: &turtle::IRI,
+    predicate: &turtle::IRI,
+    bulk_data_path: &str,
     dicom_object: &InMemDicomObject,
-    file_name: &str,
-    config: &Config,
-    depth: u8,
-) -> (Option<String>, u8) {
+    data_element: &InMemElement,
+    document: &Document,
+) -> Result<(), Box<dyn Error>> {
+    let bulk_data = BulkData::from_element(data_element)?;
+    if let Some(bulk_data_dir) = document.bulk_data_dir {
+        bulk_data
+            .store(bulk_data_dir)
+            .map_err(|e| CategorizedError::new(ErrorCategory::BulkData, e))?;
+    }
+    let node = turtle::skolem_iri(document.namespace, bulk_data_path);
+    triple_writer.write_triple(turtle::triple(
+        subject,
+        predicate,
+        &turtle::TripleObject::from(node.clone()),
+    ))?;
+    let mut properties = vec![
+        (
+            &*RDF_TYPE_IRI,
+            turtle::TripleObject::from(BULK_DATA_IRI.clone()),
+        ),
+        (
+            &*BYTE_LENGTH_IRI,
+            turtle::TripleObject::from(turtle::PlainLiteral::Integer(bulk_data.byte_length as i64)),
+        ),
+    ];
+    // Digests of bulk data are keyed like source hashes when de-identifying, as they would
+    // otherwise identify the original values.
+    let sha256 = match (
+        &document.config.deidentification,
+        &document.config.pseudonymization,
+    ) {
+        (Some(_), Some(pseudonymization)) => Some(pseudonymization.hash(&bulk_data.sha256)),
+        (Some(_), None) => None,
+        (None, _) => Some(bulk_data.sha256.clone()),
+    };
+    if let Some(sha256) = sha256 {
+        properties.push((
+            &*SHA256_IRI,
+            turtle::TripleObject::from(turtle::PlainLiteral::String(sha256)),
+        ));
+    }
+    if let Some(fragment_count) = bulk_data.fragment_count {
+        properties.push((
+            &*FRAGMENT_COUNT_IRI,
+            turtle::TripleObject::from(turtle::PlainLiteral::Integer(fragment_count as i64)),
+        ));
+    }
+    if data_element.header().tag == Tag(0x7FE0, 0x0010) {
+        properties.push((
+            &*TRANSFER_SYNTAX_IRI,
+            turtle::TripleObject::from(turtle::PlainLiteral::String(
+                document.transfer_syntax.to_string(),
+            )),
+        ));
+        for (tag, attribute_predicate) in PIXEL_DATA_ATTRIBUTES.iter() {
+            if let Some(value) = dicom_object
+                .element(*tag)
+                .ok()
+                .and_then(|e| e.to_int::<i64>().ok())
+            {
+                properties.push((
+                    attribute_predicate,
+                    turtle::TripleObject::from(turtle::PlainLiteral::Integer(value)),
+                ));
+            }
+        }
+    }
+    for (property, object) in &properties {
+        triple_writer.write_triple(turtle::triple(&node, property, object))?;
+    }
+    if data_element.header().tag == Tag(0x0042, 0x0011) {
+        write_encapsulated_document(
+            triple_writer,
+            &node,
+            bulk_data_path,
+            dicom_object,
+            &bulk_data,
+            document,
+        )?;
+    }
+    Ok(())
+}
+
+// Coordinates of SCOORD, SCOORD3D and TCOORD content items are also written as a GeoSPARQL
+// geometry of the content item.
+fn write_geometry(
+    triple_writer: &mut impl TripleSink,
+    subject: &turtle::IRI,
+    geometry_path: &str,
+    wkt: String,
+    document: &Document,
+) -> std::io::Result<()> {
+    let node = turtle::skolem_iri(document.namespace, geometry_path);
+    let triples = [
+        (
+            subject,
+            &*HAS_GEOMETRY_IRI,
+            turtle::TripleObject::from(node.clone()),
+        ),
+        (
+            &node,
+            &*RDF_TYPE_IRI,
+            turtle::TripleObject::from(turtle::IRI::prefix("geo", "Geometry")),
+        ),
+        (
+            &node,
+            &*AS_WKT_IRI,
+            turtle::TripleObject::from(turtle::TypedLiteral::new(
+                wkt,
+                turtle::IRI::prefix("geo", "wktLiteral"),
+            )),
+        ),
+    ];
+    for (subject, predicate, object) in &triples {
+        triple_writer.write_triple(turtle::triple(subject, predicate, object))?;
+    }
+    Ok(())
+}
+
+// The node of the content item a Referenced Content Item Identifier points to, if it is written.
+// The root is 1, each further value is a position in the Content Sequence of the item before.
+fn referenced_content_item(
+    document: &Document,
+    positions: &[u32],
+) -> Result<Option<turtle::IRI>, String> {
+    let identifier = positions
+        .iter()
+        .map(|position| position.to_string())
+        .collect::<Vec<_>>()
+        .join("\\");
+    let Some((1, positions)) = positions.split_first() else {
+        return Err(format!(
+            "Referenced Content Item Identifier {} does not start at the root",
+            identifier
+        ));
+    };
+    if positions.is_empty() {
+        return Ok(Some(document.root.subject.clone()));
+    }
+    // The profile replaces or removes Content Sequences it does not keep or clean.
+    let config = document.config;
+    if !matches!(
+        config.tag_action(CONTENT_SEQUENCE, None),
+        None | Some(ProfileAction::K | ProfileAction::C)
+    ) {
+        return Ok(None);
+    }
+    let dropped = |action: Option<RedactionAction>| action == Some(RedactionAction::DropSubtree);
+    let mut item = document.dataset;
+    let mut template = declared_template(item).or(document.root.template);
+    let mut path = document.root.path.to_string();
+    let mut tag_path = document.root.tag_path.to_string();
+    for position in positions {
+        let index = position.checked_sub(1).ok_or_else(|| {
+            format!(
+                "Referenced Content Item Identifier {} has a position 0",
+                identifier
+            )
+        })?;
+        item = item
+            .element(CONTENT_SEQUENCE)
+            .ok()
+            .and_then(|e| e.items())
+            .and_then(|items| items.get(index as usize))
+            .ok_or_else(|| {
+                format!(
+                    "Referenced Content Item Identifier {} points to no content item",
+                    identifier
+                )
+            })?;
+        template = declared_template(item).or(template);
+        path = format!("{}/0040A730/{}/item", path, index);
+        tag_path = if tag_path.is_empty() {
+            String::from("0040A730")
+        } else {
+            format!("{}/0040A730", tag_path)
+        };
+        if dropped(redaction::tag_path_action(
+            &config.redaction_rules,
+            &tag_path,
+        )) || dropped(content_item_redaction(config, item, template).map(|r| r.action))
+        {
+            return Ok(None);
+        }
+    }
+    Ok(Some(turtle::skolem_iri(document.namespace, &path)))
+}
+
+// Items referencing another content item link to its node. The positions of the identifier are
+// only meaningful in order, which triples do not keep.
+fn write_referenced_content_item(
+    triple_writer: &mut impl TripleSink,
+    error_writer: &mut ErrorWriter<impl Write>,
+    subject: &turtle::IRI,
+    item: &InMemDicomObject,
+    tag_path: &str,
+    document: &Document,
+) -> std::io::Result<()> {
+    let Ok(data_element) = item.element(REFERENCED_CONTENT_ITEM_IDENTIFIER) else {
+        return Ok(());
+    };
+    if data_element.value().is_empty() {
+        return Ok(());
+    }
+    let target = data_element
+        .value()
+        .uint32_slice()
+        .map_err(|e| e.to_string())
+        .and_then(|positions| referenced_content_item(document, positions));
+    match target {
+        Ok(Some(target)) => triple_writer.write_triple(turtle::triple(
+            subject,
+            &REFERENCED_CONTENT_ITEM_IRI,
+            &turtle::TripleObject::from(target),
+        )),
+        Ok(None) => Ok(()),
+        Err(e) => log_error(
+            error_writer,
+            document,
+            &format!("{}/0040DB73", tag_path),
+            data_element,
+            ErrorCategory::InvalidValue,
+            e,
+        ),
+    }
+}
+
+// Text of encapsulated PDF and CDA documents, next to their bulk data node.
+fn write_encapsulated_document(
+    triple_writer: &mut impl TripleSink,
+    node: &turtle::IRI,
+    path: &str,
+    dicom_object: &InMemDicomObject,
+    bulk_data: &BulkData,
+    document: &Document,
+) -> Result<(), Box<dyn Error>> {
+    // Encapsulated documents only occur at the top level of a dataset.
+    let language = declared_language(dicom_object).or(document.config.language.as_deref());
+    let text = |s: String| text_literal(scrub_text(document, s), language);
+    let bytes = bulk_data.bytes();
+    match encapsulated::document_kind(string_value(dicom_object, Tag(0x0042, 0x0012)), &bytes) {
+        // Their content is not covered by the de-identification profile.
+        Some(_) if document.config.deidentification.is_some() => {
+            return Err(CategorizedError::new(
+                ErrorCategory::EncapsulatedDocument,
+                "Text of the encapsulated document was not extracted from a de-identified document",
+            )
+            .into());
+        }
+        Some(DocumentKind::Pdf) => {
+            let pdf_text = encapsulated::pdf_text(&bytes)
+                .map_err(|e| CategorizedError::new(ErrorCategory::EncapsulatedDocument, e))?;
+            let object = turtle::TripleObject::from(text(pdf_text));
+            triple_writer.write_triple(turtle::triple(node, &TEXT_IRI, &object))?;
+        }
+        Some(DocumentKind::Cda) => encapsulated::write_cda_triples(
+            triple_writer,
+            document.namespace,
+            node,
+            path,
+            &bytes,
+            &text,
+        )
+        .map_err(|e| CategorizedError::new(ErrorCategory::EncapsulatedDocument, e))?,
+        None => {}
+    }
+    Ok(())
+}
+
+pub fn write_triples(
+    triple_writer: &mut impl TripleSink,
+    error_writer: &mut ErrorWriter<impl Write>,
+    node: &Node,
+    dicom_object: &InMemDicomObject,
+    document: &Document,
+) -> u8 {
+    let config = document.config;
+    let Node {
+        subject,
+        path,
+        depth,
+        ..
+    } = *node;
+    let language = declared_language(dicom_object).or(node.language);
+    let template = declared_template(dicom_object).or(node.template);
+    let item_redaction = content_item_redaction(config, dicom_object, template);
+    if item_redaction.is_some_and(|r| r.action == RedactionAction::DropSubtree) {
+        return depth;
+    }
     let mut max_depth_seen = depth;
-    let mut carry: Option<String> = None;
+    let private_creators = private_creators(dicom_object);
     for data_element in dicom_object.iter() {
         if data_element.value().is_empty() {
             continue;
         }
-        let group = data_element.header().tag.group();
-        let element = data_element.header().tag.element();
-        let predicate = turtle::IRI::prefix("dicom2rdf", format!("{:04X}{:04X}", group, element,));
-        if group == 0x7FE0 && element == 0x0010 {
-            debug!("VR: {}", data_element.vr());
-            let object =
-                turtle::TripleObject::from(turtle::PlainLiteral::String("<pixel data>".into()));
-            let _ = writeln!(
-                triple_writer,
-                "{}",
-                turtle::triple(subject, &predicate, &object)
+        let tag = data_element.header().tag;
+        let group = tag.group();
+        let element = tag.element();
+        let tag_hex = format!("{:04X}{:04X}", group, element);
+        let creator = private_creator(&private_creators, tag);
+        let predicate = match creator {
+            Some(creator) => turtle::IRI::prefix_path(
+                "dicom2rdf",
+                &[
+                    "private",
+                    creator,
+                    &format!("{:04X}xx{:02X}", group, element & 0xFF),
+                ],
+            ),
+            None => turtle::IRI::prefix("dicom2rdf", &tag_hex),
+        };
+        let element_tag_path = if node.tag_path.is_empty() {
+            tag_hex.clone()
+        } else {
+            format!("{}/{}", node.tag_path, tag_hex)
+        };
+        if is_private_attribute(tag) && creator.is_none() {
+            let _ = log_error(
+                error_writer,
+                document,
+                &element_tag_path,
+                data_element,
+                ErrorCategory::MissingPrivateCreator,
+                "Private attribute without private creator",
             );
-            continue;
         }
-        if config
-            .forbidden_dicom_tags
-            .contains(&data_element.header().tag())
+        let tag_label = format!("({:04X},{:04X})", group, element);
+        let tag_action = config.tag_action(tag, creator);
+        if let Some(action) = tag_action {
+            document.report.borrow_mut().record(tag, action);
+        }
+        if matches!(document.date_shift, Some(DateShift::Remove))
+            && matches!(data_element.vr(), VR::DA | VR::DT)
         {
-            let _ = writeln!(
-                triple_writer,
-                "{}",
-                turtle::triple(
-                    subject,
-                    &predicate,
-                    &turtle::TripleObject::PlainLiteral(turtle::PlainLiteral::String(format!(
-                        "<({:04X},{:04X})>",
-                        group, element,
-                    )))
-                )
+            continue;
+        }
+        // Pseudonyms stand in for the values the profile empties or replaces, and for the
+        // values of documents that are pseudonymized only.
+        let pseudonymization = config
+            .pseudonymization
+            .as_ref()
+            .filter(|pseudonymization| pseudonymization.pseudonymizes(tag))
+            .filter(|_| matches!(tag_action, None | Some(ProfileAction::Z | ProfileAction::U)));
+        let replacement = match (tag_action, pseudonymization) {
+            (_, Some(pseudonymization)) => Some(replaced_values(data_element, |value| {
+                pseudonymization.pseudonym(tag, value)
+            })),
+            (Some(ProfileAction::X), _) => continue,
+            (Some(ProfileAction::Z), _) if data_element.vr() == VR::SQ => continue,
+            (Some(ProfileAction::Z), _) => Some(Ok(vec![String::new()])),
+            (Some(ProfileAction::U), _) if data_element.vr() == VR::UI => {
+                Some(replaced_values(data_element, |uid| {
+                    replacement_uid(config, uid)
+                }))
+            }
+            // Sequences to be cleaned are kept, the profile applies to their items.
+            (Some(ProfileAction::C), _) if data_element.vr() == VR::SQ => None,
+            // Dates are cleaned by shifting them.
+            (Some(ProfileAction::C), _)
+                if matches!(document.date_shift, Some(DateShift::Days(_)))
+                    && matches!(data_element.vr(), VR::DA | VR::DT | VR::TM) =>
+            {
+                None
+            }
+            (Some(ProfileAction::D | ProfileAction::C | ProfileAction::U), _) => {
+                Some(Ok(vec![format!("<{}>", tag_label)]))
+            }
+            (Some(ProfileAction::K) | None, _) => None,
+        };
+        // Elements are dropped unless every value has a replacement.
+        if let Some(Err(e)) = replacement {
+            let _ = log_error(
+                error_writer,
+                document,
+                &element_tag_path,
+                data_element,
+                ErrorCategory::Pseudonymization,
+                format!("Dropped element that could not be pseudonymized: {}", e),
             );
             continue;
         }
+        let replacement = replacement.and_then(Result::ok);
+        if let Some(values) = replacement {
+            for value in values {
+                let object = turtle::TripleObject::from(turtle::PlainLiteral::String(value));
+                let _ = triple_writer.write_triple(turtle::triple(subject, &predicate, &object));
+            }
+            continue;
+        }
+        let redaction = node
+            .redaction
+            .or_else(|| {
+                redaction::tag_path_action(&config.redaction_rules, &element_tag_path).map(
+                    |action| Redaction {
+                        action,
+                        label: &tag_label,
+                    },
+                )
+            })
+            .or(item_redaction.filter(|_| CONTENT_ITEM_VALUE_TAGS.contains(&tag)));
+        if redaction.is_some_and(|r| r.action == RedactionAction::DropSubtree) {
+            continue;
+        }
+        if let Some(redaction) = redaction
+            && data_element.vr() != VR::SQ
+        {
+            let result = redacted_values(document, data_element, redaction).and_then(|values| {
+                for value in values {
+                    let object = turtle::TripleObject::from(turtle::PlainLiteral::String(value));
+                    triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
+                }
+                Ok(())
+            });
+            if let Err(e) = result {
+                let category = value_error_category(data_element.vr(), &*e);
+                let _ = log_error(
+                    error_writer,
+                    document,
+                    &element_tag_path,
+                    data_element,
+                    category,
+                    e,
+                );
+            }
+            continue;
+        }
         if let Err(e) = (|| -> Result<(), Box<dyn Error>> {
             match data_element.vr() {
                 VR::AS => {
                     let age_str = data_element.value().string()?.trim();
                     let years = age_string_to_years(age_str)?;
-                    let object = turtle::TripleObject::from(turtle::PlainLiteral::Float(years));
-                    writeln!(
-                        triple_writer,
-                        "{}",
-                        turtle::triple(subject, &predicate, &object)
-                    )?;
+                    let object = turtle::TripleObject::from(float_to_typed_literal(years));
+                    triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
+                }
+                VR::AT => {
+                    for val in data_element.value().tags()? {
+                        let object = turtle::TripleObject::from(turtle::IRI::prefix(
+                            "dicom2rdf",
+                            format!("{:04X}{:04X}", val.group(), val.element()),
+                        ));
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
+                    }
                 }
                 VR::DA => {
                     for val in data_element.value().to_multi_date()? {
+                        let val = match document.date_shift {
+                            Some(DateShift::Days(days)) => shift_date(&val, days)?,
+                            _ => val,
+                        };
                         let object = turtle::TripleObject::from(iso_string_to_typed_literal(
                             &date_to_iso(&val),
                         ));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(subject, &predicate, &object)
-                        )?;
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
                     }
                 }
                 VR::DT => {
                     for val in data_element.value().to_multi_datetime()? {
+                        let val = match document.date_shift {
+                            Some(DateShift::Days(days)) => shift_datetime(&val, days)?,
+                            _ => val,
+                        };
                         let object = turtle::TripleObject::from(iso_string_to_typed_literal(
                             &datetime_to_iso(&val),
                         ));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(subject, &predicate, &object)
-                        )?;
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
                     }
                 }
                 VR::TM => {
@@ -117,45 +837,35 @@ pub fn write_triples(
                             time_to_iso(&val),
                             turtle::IRI::prefix("xsd", "time"),
                         ));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(subject, &predicate, &object)
-                        )?;
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
                     }
                 }
-                VR::AE | VR::CS | VR::LT | VR::ST | VR::UI => {
+                VR::AE | VR::CS | VR::UI => {
                     for val in data_element.value().strings()? {
                         let s = val.trim().trim_end_matches('\0');
                         let object =
                             turtle::TripleObject::from(turtle::PlainLiteral::String(s.into()));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(subject, &predicate, &object)
-                        )?;
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
+                    }
+                }
+                VR::LT | VR::ST | VR::UC => {
+                    for s in text_values(data_element)? {
+                        let s = scrub_text(document, s);
+                        let object = turtle::TripleObject::from(text_literal(s, language));
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
                     }
                 }
                 VR::LO => {
-                    for val in data_element.value().strings()? {
-                        let s = val.trim().trim_end_matches('\0');
+                    for s in text_values(data_element)? {
+                        let s = s.as_str();
                         let object =
                             turtle::TripleObject::from(turtle::PlainLiteral::String(s.into()));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(subject, &predicate, &object)
-                        )?;
-                        if data_element.header().tag() == Tag(0x0008, 0x0104)
-                            && config.forbidden_code_meanings.contains(s)
-                        {
-                            carry = Some(s.to_string());
-                        }
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
                     }
                 }
                 VR::SH => {
-                    for val in data_element.value().strings()? {
-                        let val = val.trim().trim_end_matches('\0');
+                    for val in text_values(data_element)? {
+                        let val = val.as_str();
                         let object = if group == 0x0008 && element == 0x0102 {
                             turtle::TripleObject::from(turtle::IRI::full(
                                 config
@@ -172,54 +882,27 @@ pub fn write_triples(
                         } else {
                             turtle::TripleObject::from(turtle::PlainLiteral::String(val.into()))
                         };
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(subject, &predicate, &object)
-                        )?;
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
                     }
                 }
                 VR::DS => {
-                    // TODO: DS does potentially not fit in f64 - should probably use rust_decimal.
                     for val in data_element.value().strings()? {
-                        let parsed_val: f64 = val.trim().parse()?;
-                        if parsed_val.is_nan() || parsed_val.is_infinite() {
-                            // Create NaN or inf literals once https://github.com/ad-freiburg/qlever/issues/2303 is fixed.
-                            continue;
-                        }
-                        let object =
-                            turtle::TripleObject::from(turtle::PlainLiteral::Float(parsed_val));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(subject, &predicate, &object)
-                        )?;
+                        let object = turtle::TripleObject::from(decimal_string_to_typed_literal(
+                            val.trim(),
+                        )?);
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
                     }
                 }
                 VR::FL => {
                     for val in data_element.value().float32_slice()? {
-                        let object =
-                            turtle::TripleObject::from(turtle::PlainLiteral::Float(*val as f64));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(subject, &predicate, &object)
-                        )?;
+                        let object = turtle::TripleObject::from(float_to_typed_literal(*val));
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
                     }
                 }
                 VR::FD => {
                     for val in data_element.value().float64_slice()? {
-                        let object = if val.is_nan() || val.is_infinite() {
-                            // Create NaN or inf literals once https://github.com/ad-freiburg/qlever/issues/2303 is fixed.
-                            continue;
-                        } else {
-                            turtle::TripleObject::from(turtle::PlainLiteral::Float(*val))
-                        };
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(subject, &predicate, &object)
-                        )?;
+                        let object = turtle::TripleObject::from(float_to_typed_literal(*val));
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
                     }
                 }
                 VR::IS => {
@@ -227,107 +910,98 @@ pub fn write_triples(
                         let parsed: i64 = val.trim().parse()?;
                         let object =
                             turtle::TripleObject::from(turtle::PlainLiteral::Integer(parsed));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(subject, &predicate, &object)
-                        )?;
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
                     }
                 }
-                VR::OW => {
-                    let object = turtle::TripleObject::from(turtle::PlainLiteral::String(
-                        "<OW>".to_string(),
-                    ));
-                    writeln!(
-                        triple_writer,
-                        "{}",
-                        turtle::triple(subject, &predicate, &object)
-                    )?;
-                }
+                VR::OB | VR::OD | VR::OF | VR::OL | VR::OV | VR::OW | VR::UN => write_bulk_data(
+                    triple_writer,
+                    subject,
+                    &predicate,
+                    &format!("{}/{}", path, tag_hex),
+                    dicom_object,
+                    data_element,
+                    document,
+                )?,
                 VR::PN => {
-                    let pn = data_element.value().to_person_name()?;
-                    let bn = turtle::create_blank_node();
-
-                    writeln!(
-                        triple_writer,
-                        "{}",
-                        turtle::triple(
+                    let texts = text_values(data_element)?;
+                    for (i, text) in texts.iter().enumerate() {
+                        if text.is_empty() {
+                            continue;
+                        }
+                        let name_path = format!("{}/{}/{}", path, tag_hex, i);
+                        let name_node = turtle::skolem_iri(document.namespace, &name_path);
+                        // The name node is linked by its attribute, which tells the Patient's
+                        // Name apart from other names of the same dataset.
+                        triple_writer.write_triple(turtle::triple(
                             subject,
-                            &*PERSON_NAME_IRI,
-                            &turtle::TripleObject::from(bn.clone())
-                        )
-                    )?;
-                    if let Some(family) = pn.family() {
-                        let object = turtle::TripleObject::from(turtle::PlainLiteral::String(
-                            family.to_string(),
-                        ));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(&bn, &*PN_FAMILY_IRI, &object)
-                        )?;
-                    }
-                    if let Some(middle) = pn.middle() {
-                        let object = turtle::TripleObject::from(turtle::PlainLiteral::String(
-                            middle.to_string(),
-                        ));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(&bn, &*PN_MIDDLE_IRI, &object)
-                        )?;
-                    }
-                    if let Some(given) = pn.given() {
-                        let object = turtle::TripleObject::from(turtle::PlainLiteral::String(
-                            given.to_string(),
-                        ));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(&bn, &*PN_GIVEN_IRI, &object)
-                        )?;
-                    }
-                    if let Some(prefix) = pn.prefix() {
-                        let object = turtle::TripleObject::from(turtle::PlainLiteral::String(
-                            prefix.to_string(),
-                        ));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(&bn, &*PN_PREFIX_IRI, &object)
-                        )?;
-                    }
-                    if let Some(suffix) = pn.suffix() {
-                        let object = turtle::TripleObject::from(turtle::PlainLiteral::String(
-                            suffix.to_string(),
-                        ));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(&bn, &*PN_SUFFIX_IRI, &object)
-                        )?;
+                            &predicate,
+                            &turtle::TripleObject::from(name_node.clone()),
+                        ))?;
+                        // Alphabetic, ideographic and phonetic component groups are separated
+                        // by '='.
+                        for (group, (group_name, group_predicate)) in
+                            text.split('=').zip(PN_GROUPS.iter())
+                        {
+                            let pn = PersonName::from_text(group);
+                            let components = [
+                                (pn.family(), &*PN_FAMILY_IRI),
+                                (pn.middle(), &*PN_MIDDLE_IRI),
+                                (pn.given(), &*PN_GIVEN_IRI),
+                                (pn.prefix(), &*PN_PREFIX_IRI),
+                                (pn.suffix(), &*PN_SUFFIX_IRI),
+                            ]
+                            .map(|(component, predicate)| {
+                                (
+                                    component.map(str::trim).filter(|c| !c.is_empty()),
+                                    predicate,
+                                )
+                            });
+                            if components.iter().all(|(component, _)| component.is_none()) {
+                                continue;
+                            }
+                            let group_node = turtle::skolem_iri(
+                                document.namespace,
+                                &format!("{}/{}", name_path, group_name),
+                            );
+                            triple_writer.write_triple(turtle::triple(
+                                &name_node,
+                                group_predicate,
+                                &turtle::TripleObject::from(group_node.clone()),
+                            ))?;
+                            for (component, predicate) in components {
+                                if let Some(component) = component {
+                                    let object = turtle::TripleObject::from(
+                                        turtle::PlainLiteral::String(component.to_string()),
+                                    );
+                                    triple_writer.write_triple(turtle::triple(
+                                        &group_node,
+                                        predicate,
+                                        &object,
+                                    ))?;
+                                }
+                            }
+                        }
                     }
                 }
                 VR::SL => {
                     for val in data_element.value().int32_slice()? {
                         let object =
                             turtle::TripleObject::from(turtle::PlainLiteral::Integer(*val as i64));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(subject, &predicate, &object)
-                        )?;
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
                     }
                 }
                 VR::SS => {
                     for val in data_element.value().int16_slice()? {
                         let object =
                             turtle::TripleObject::from(turtle::PlainLiteral::Integer(*val as i64));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(subject, &predicate, &object)
-                        )?;
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
+                    }
+                }
+                VR::SV => {
+                    for val in data_element.value().int64_slice()? {
+                        let object =
+                            turtle::TripleObject::from(turtle::PlainLiteral::Integer(*val));
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
                     }
                 }
                 VR::SQ => {
@@ -337,64 +1011,80 @@ pub fn write_triples(
                         .iter()
                         .enumerate()
                     {
-                        let sequence_bn = turtle::create_blank_node();
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(
-                                subject,
-                                &predicate,
-                                &turtle::TripleObject::from(sequence_bn.clone())
-                            )
-                        )?;
-                        if group == 0x0040 && element == 0xA730 {
-                            writeln!(
-                                triple_writer,
-                                "{}",
-                                turtle::triple(
-                                    &sequence_bn,
-                                    &*INDEX_IRI,
-                                    &turtle::TripleObject::from(turtle::PlainLiteral::Integer(
-                                        i as i64
-                                    ))
-                                )
-                            )?;
-                            let item_content_bn = turtle::create_blank_node();
-                            writeln!(
+                        let is_content_item = tag == CONTENT_SEQUENCE;
+                        // Dropped items leave no trace, not even the link to their sequence.
+                        if content_item_redaction(
+                            config,
+                            item,
+                            declared_template(item).or(template),
+                        )
+                        .is_some_and(|r| r.action == RedactionAction::DropSubtree)
+                        {
+                            continue;
+                        }
+                        let sequence_path = format!("{}/{}/{}", path, tag_hex, i);
+                        let sequence_bn = turtle::skolem_iri(document.namespace, &sequence_path);
+                        triple_writer.write_triple(turtle::triple(
+                            subject,
+                            &predicate,
+                            &turtle::TripleObject::from(sequence_bn.clone()),
+                        ))?;
+                        if is_content_item {
+                            triple_writer.write_triple(turtle::triple(
+                                &sequence_bn,
+                                &INDEX_IRI,
+                                &turtle::TripleObject::from(turtle::PlainLiteral::Integer(
+                                    i as i64,
+                                )),
+                            ))?;
+                            let item_content_path = format!("{}/item", sequence_path);
+                            let item_content_bn =
+                                turtle::skolem_iri(document.namespace, &item_content_path);
+                            triple_writer.write_triple(turtle::triple(
+                                &sequence_bn,
+                                &ITEM_IRI,
+                                &turtle::TripleObject::from(item_content_bn.clone()),
+                            ))?;
+                            write_referenced_content_item(
                                 triple_writer,
-                                "{}",
-                                turtle::triple(
-                                    &sequence_bn,
-                                    &*ITEM_IRI,
-                                    &turtle::TripleObject::from(item_content_bn.clone())
-                                )
+                                error_writer,
+                                &item_content_bn,
+                                item,
+                                &element_tag_path,
+                                document,
                             )?;
-                            let (result, max_depth_child) = write_triples(
+                            let max_depth_child = write_triples(
                                 triple_writer,
                                 error_writer,
-                                &item_content_bn,
+                                &Node {
+                                    subject: &item_content_bn,
+                                    path: &item_content_path,
+                                    tag_path: &element_tag_path,
+                                    depth: depth + 1,
+                                    language,
+                                    template,
+                                    redaction,
+                                },
                                 item,
-                                file_name,
-                                config,
-                                depth + 1,
+                                document,
                             );
                             max_depth_seen = max_depth_seen.max(max_depth_child);
-                            if carry.is_none() {
-                                carry = result;
-                            }
                         } else {
-                            let (result, max_depth_child) = write_triples(
+                            let max_depth_child = write_triples(
                                 triple_writer,
                                 error_writer,
-                                &sequence_bn,
+                                &Node {
+                                    subject: &sequence_bn,
+                                    path: &sequence_path,
+                                    tag_path: &element_tag_path,
+                                    depth,
+                                    language,
+                                    template,
+                                    redaction,
+                                },
                                 item,
-                                file_name,
-                                config,
-                                depth,
+                                document,
                             );
-                            if carry.is_none() {
-                                carry = result;
-                            }
                             max_depth_seen = max_depth_seen.max(max_depth_child)
                         }
                     }
@@ -403,68 +1093,76 @@ pub fn write_triples(
                     for val in data_element.value().uint32_slice()? {
                         let object =
                             turtle::TripleObject::from(turtle::PlainLiteral::Integer(*val as i64));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(subject, &predicate, &object)
-                        )?;
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
                     }
                 }
-                VR::OB | VR::UN => {
-                    let object = turtle::TripleObject::from(turtle::PlainLiteral::String(
-                        "<octet stream>".to_string(),
-                    ));
-                    writeln!(
-                        triple_writer,
-                        "{}",
-                        turtle::triple(subject, &predicate, &object)
-                    )?;
-                }
                 VR::US => {
                     for val in data_element.value().uint16_slice()?.iter() {
                         let object =
                             turtle::TripleObject::from(turtle::PlainLiteral::Integer(*val as i64));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(subject, &predicate, &object)
-                        )?;
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
                     }
                 }
-                VR::UT => {
+                VR::UR => {
                     for val in data_element.value().strings()? {
-                        let mut s = val.trim().trim_end_matches('\0').to_string();
-
-                        if group == 0x0040 && element == 0xA160 {
-                            if let Some(ref replacement) = carry {
-                                s = format!("<{}>", replacement);
-                            }
+                        let s = val.trim().trim_end_matches('\0');
+                        if !turtle::is_absolute_iri(s) {
+                            return Err(format!("Invalid URI '{}'", s).into());
                         }
-
-                        let object = turtle::TripleObject::from(turtle::PlainLiteral::String(s));
-                        writeln!(
-                            triple_writer,
-                            "{}",
-                            turtle::triple(subject, &predicate, &object)
-                        )?;
+                        let object = turtle::TripleObject::from(turtle::IRI::full(s));
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
                     }
                 }
-                _ => {
-                    // Unsupported VR
+                VR::UV => {
+                    for val in data_element.value().uint64_slice()? {
+                        let object = match i64::try_from(*val) {
+                            Ok(n) => turtle::TripleObject::from(turtle::PlainLiteral::Integer(n)),
+                            Err(_) => turtle::TripleObject::from(turtle::TypedLiteral::new(
+                                val.to_string(),
+                                turtle::IRI::prefix("xsd", "integer"),
+                            )),
+                        };
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
+                    }
                 }
+                VR::UT => {
+                    for s in text_values(data_element)? {
+                        let s = scrub_text(document, s);
+                        let object = turtle::TripleObject::from(text_literal(s, language));
+                        triple_writer.write_triple(turtle::triple(subject, &predicate, &object))?;
+                    }
+                }
+            }
+            if let Some(wkt) = geometry::wkt(document.namespace, dicom_object, data_element)? {
+                write_geometry(
+                    triple_writer,
+                    subject,
+                    &format!("{}/{}/geometry", path, tag_hex),
+                    wkt,
+                    document,
+                )?;
+            }
+            // TCOORD time offsets are written as durations as well, which SPARQL compares as
+            // times.
+            for offset in geometry::time_offsets(dicom_object, data_element)? {
+                let object = turtle::TripleObject::from(turtle::TypedLiteral::new(
+                    offset,
+                    turtle::IRI::prefix("xsd", "duration"),
+                ));
+                triple_writer.write_triple(turtle::triple(subject, &TIME_OFFSET_IRI, &object))?;
             }
             Ok(())
         })() {
-            let _ = writeln!(
+            let category = value_error_category(data_element.vr(), &*e);
+            let _ = log_error(
                 error_writer,
-                "{}: ({:04X},{:04X}) {}: {}",
-                file_name,
-                group,
-                element,
-                data_element.vr(),
-                e
+                document,
+                &element_tag_path,
+                data_element,
+                category,
+                e,
             );
         }
     }
-    (carry, max_depth_seen)
+    max_depth_seen
 }
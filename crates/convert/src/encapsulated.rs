@@ -0,0 +1,123 @@
+use crate::turtle::{self, TripleSink};
+use std::error::Error;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::LazyLock;
+
+static INDEX_IRI: LazyLock<turtle::IRI> =
+    LazyLock::new(|| turtle::IRI::prefix("dicom2rdf", "index"));
+static TEXT_IRI: LazyLock<turtle::IRI> = LazyLock::new(|| turtle::IRI::prefix("dicom2rdf", "text"));
+
+pub enum DocumentKind {
+    Pdf,
+    Cda,
+}
+
+// The MIME Type of Encapsulated Document (0042,0012) decides, the content is only sniffed when it
+// is missing.
+pub fn document_kind(mime_type: Option<&str>, bytes: &[u8]) -> Option<DocumentKind> {
+    match mime_type.map(|m| m.to_ascii_lowercase()) {
+        Some(m) if m == "application/pdf" => Some(DocumentKind::Pdf),
+        Some(m) if m == "text/xml" || m == "application/xml" => Some(DocumentKind::Cda),
+        Some(_) => None,
+        None if bytes.starts_with(b"%PDF") => Some(DocumentKind::Pdf),
+        None if bytes.trim_ascii_start().starts_with(b"<") => Some(DocumentKind::Cda),
+        None => None,
+    }
+}
+
+// Encapsulated documents are padded to an even length, parsers accept a trailing space but not
+// a NUL.
+fn trim_padding(bytes: &[u8]) -> &[u8] {
+    bytes.strip_suffix(b"\0").unwrap_or(bytes)
+}
+
+pub fn pdf_text(bytes: &[u8]) -> Result<String, String> {
+    // The PDF parser panics on some malformed files, which must not abort the conversion.
+    let text = catch_unwind(AssertUnwindSafe(|| {
+        pdf_extract::extract_text_from_mem(trim_padding(bytes))
+    }))
+    .map_err(|_| "PDF text extraction panicked".to_string())?
+    .map_err(|e| format!("Failed to extract PDF text: {}", e))?;
+    Ok(text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+// CDA elements become nodes linked by dicom2rdf:cda\/<element name> and carrying their attributes
+// as dicom2rdf:cda_attribute\/<attribute name>. Text directly inside an element is joined into a
+// dicom2rdf:text literal, passed through `text` first.
+pub fn write_cda_triples(
+    triple_writer: &mut impl TripleSink,
+    namespace: &str,
+    subject: &turtle::IRI,
+    path: &str,
+    bytes: &[u8],
+    text: &dyn Fn(String) -> turtle::PlainLiteral,
+) -> Result<(), Box<dyn Error>> {
+    let xml = std::str::from_utf8(trim_padding(bytes))?;
+    let cda = roxmltree::Document::parse(xml)?;
+    write_element(
+        triple_writer,
+        namespace,
+        subject,
+        &format!("{}/cda", path),
+        0,
+        cda.root_element(),
+        text,
+    )
+}
+
+fn write_element(
+    triple_writer: &mut impl TripleSink,
+    namespace: &str,
+    parent: &turtle::IRI,
+    path: &str,
+    index: usize,
+    element: roxmltree::Node,
+    text: &dyn Fn(String) -> turtle::PlainLiteral,
+) -> Result<(), Box<dyn Error>> {
+    let node = turtle::skolem_iri(namespace, path);
+    triple_writer.write_triple(turtle::triple(
+        parent,
+        &turtle::IRI::prefix_path("dicom2rdf", &["cda", element.tag_name().name()]),
+        &turtle::TripleObject::from(node.clone()),
+    ))?;
+    triple_writer.write_triple(turtle::triple(
+        &node,
+        &INDEX_IRI,
+        &turtle::TripleObject::from(turtle::PlainLiteral::Integer(index as i64)),
+    ))?;
+    for attribute in element.attributes() {
+        triple_writer.write_triple(turtle::triple(
+            &node,
+            &turtle::IRI::prefix_path("dicom2rdf", &["cda_attribute", attribute.name()]),
+            &turtle::TripleObject::from(turtle::PlainLiteral::String(
+                attribute.value().to_string(),
+            )),
+        ))?;
+    }
+    let content = element
+        .children()
+        .filter(|child| child.is_text())
+        .filter_map(|child| child.text())
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ");
+    if !content.is_empty() {
+        triple_writer.write_triple(turtle::triple(
+            &node,
+            &TEXT_IRI,
+            &turtle::TripleObject::from(text(content)),
+        ))?;
+    }
+    for (i, child) in element.children().filter(|c| c.is_element()).enumerate() {
+        write_element(
+            triple_writer,
+            namespace,
+            &node,
+            &format!("{}/{}", path, i),
+            i,
+            child,
+            text,
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,253 @@
+use dicom::core::dictionary::DataDictionary;
+use dicom::core::{Tag, VR};
+use dicom::dictionary_std::StandardDataDictionary;
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::io::Write;
+use std::sync::Mutex;
+
+const MAX_VALUE_LENGTH: usize = 256;
+
+// Stable names for the kinds of conversion errors. Renaming a variant breaks downstream tooling.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    UnreadableFile,
+    MissingSopInstanceUid,
+    MissingPatientId,
+    UnsupportedCharset,
+    CharsetDecoding,
+    MissingPrivateCreator,
+    InvalidDate,
+    InvalidAge,
+    InvalidNumber,
+    InvalidUri,
+    InvalidValue,
+    UnsupportedGeometry,
+    Pseudonymization,
+    EncapsulatedDocument,
+    BulkData,
+    Io,
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = serde_json::to_value(self).map_err(|_| fmt::Error)?;
+        write!(f, "{}", value.as_str().unwrap_or_default())
+    }
+}
+
+// An error whose category cannot be told from the VR of the data element it occurred in.
+#[derive(Debug)]
+pub struct CategorizedError {
+    pub category: ErrorCategory,
+    message: String,
+}
+
+impl CategorizedError {
+    pub fn new(category: ErrorCategory, message: impl fmt::Display) -> Self {
+        Self {
+            category,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for CategorizedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for CategorizedError {}
+
+pub fn value_error_category(vr: VR, e: &(dyn Error + 'static)) -> ErrorCategory {
+    if let Some(e) = e.downcast_ref::<CategorizedError>() {
+        return e.category;
+    }
+    if e.is::<std::io::Error>() {
+        return ErrorCategory::Io;
+    }
+    match vr {
+        VR::DA | VR::DT | VR::TM => ErrorCategory::InvalidDate,
+        VR::AS => ErrorCategory::InvalidAge,
+        VR::DS | VR::IS => ErrorCategory::InvalidNumber,
+        VR::UR => ErrorCategory::InvalidUri,
+        _ => ErrorCategory::InvalidValue,
+    }
+}
+
+// One line of a worker's errors.jsonl. Element fields are omitted for errors concerning the
+// whole file.
+#[derive(Serialize)]
+pub struct ConversionError<'a> {
+    pub category: ErrorCategory,
+    pub file: &'a str,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_tag"
+    )]
+    pub tag: Option<Tag>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag_path: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vr: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    pub message: String,
+}
+
+fn serialize_tag<S: Serializer>(tag: &Option<Tag>, serializer: S) -> Result<S::Ok, S::Error> {
+    match tag {
+        Some(tag) => serializer.serialize_str(&format_tag(*tag)),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn format_tag(tag: Tag) -> String {
+    format!("({:04X},{:04X})", tag.group(), tag.element())
+}
+
+impl<'a> ConversionError<'a> {
+    pub fn file(category: ErrorCategory, file: &'a str, message: impl fmt::Display) -> Self {
+        Self {
+            category,
+            file,
+            tag: None,
+            tag_path: None,
+            vr: None,
+            value: None,
+            message: message.to_string(),
+        }
+    }
+}
+
+// Raw values are cut off, so that a broken element cannot blow up the error log.
+pub fn truncated_value(value: &str) -> String {
+    match value.char_indices().nth(MAX_VALUE_LENGTH) {
+        Some((end, _)) => format!("{}...", &value[..end]),
+        None => value.to_string(),
+    }
+}
+
+#[derive(Default)]
+pub struct ErrorSummary {
+    counts: BTreeMap<(ErrorCategory, Option<Tag>), usize>,
+}
+
+impl ErrorSummary {
+    fn record(&mut self, category: ErrorCategory, tag: Option<Tag>) {
+        *self.counts.entry((category, tag)).or_default() += 1;
+    }
+
+    fn merge(&mut self, other: &ErrorSummary) {
+        for (key, count) in &other.counts {
+            *self.counts.entry(*key).or_default() += count;
+        }
+    }
+
+    pub fn totals(&self) -> BTreeMap<ErrorCategory, usize> {
+        let mut totals = BTreeMap::new();
+        for (&(category, _), count) in &self.counts {
+            *totals.entry(category).or_default() += count;
+        }
+        totals
+    }
+
+    pub fn write_tsv(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writeln!(writer, "category\ttag\tkeyword\tcount")?;
+        for (&(category, tag), count) in &self.counts {
+            let (tag, keyword) = match tag {
+                Some(tag) => (
+                    format_tag(tag),
+                    StandardDataDictionary
+                        .by_tag(tag)
+                        .map_or("", |entry| entry.alias),
+                ),
+                None => (String::new(), ""),
+            };
+            writeln!(writer, "{}\t{}\t{}\t{}", category, tag, keyword, count)?;
+        }
+        Ok(())
+    }
+}
+
+// Writes errors as JSON Lines and counts them, the counts are merged into the run's summary when
+// the worker is done. De-identified and pseudonymized runs keep original values out of the log.
+pub struct ErrorWriter<'a, W: Write> {
+    writer: W,
+    summary: ErrorSummary,
+    run_summary: &'a Mutex<ErrorSummary>,
+    omit_values: bool,
+}
+
+impl<'a, W: Write> ErrorWriter<'a, W> {
+    pub fn new(writer: W, run_summary: &'a Mutex<ErrorSummary>, omit_values: bool) -> Self {
+        Self {
+            writer,
+            summary: ErrorSummary::default(),
+            run_summary,
+            omit_values,
+        }
+    }
+
+    pub fn log(&mut self, error: &ConversionError) -> std::io::Result<()> {
+        if self.omit_values && error.value.is_some() {
+            return self.log(&ConversionError {
+                category: error.category,
+                file: error.file,
+                tag: error.tag,
+                tag_path: error.tag_path,
+                vr: error.vr,
+                value: None,
+                message: error.message.clone(),
+            });
+        }
+        self.summary.record(error.category, error.tag);
+        serde_json::to_writer(&mut self.writer, error)?;
+        writeln!(self.writer)
+    }
+}
+
+impl<W: Write> Drop for ErrorWriter<'_, W> {
+    fn drop(&mut self) {
+        self.writer.flush().ok();
+        if let Ok(mut run_summary) = self.run_summary.lock() {
+            run_summary.merge(&self.summary);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logged(omit_values: bool) -> String {
+        let run_summary = Mutex::default();
+        let mut buffer = Vec::new();
+        let mut error_writer = ErrorWriter::new(&mut buffer, &run_summary, omit_values);
+        error_writer
+            .log(&ConversionError {
+                category: ErrorCategory::InvalidDate,
+                file: "2.25.1",
+                tag: Some(Tag(0x0010, 0x0030)),
+                tag_path: Some("00100030"),
+                vr: Some("DA"),
+                value: Some("19700132".to_string()),
+                message: "Invalid date".to_string(),
+            })
+            .unwrap();
+        drop(error_writer);
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn values_are_omitted_on_request() {
+        assert!(logged(false).contains(r#""value":"19700132""#));
+        let omitted = logged(true);
+        assert!(!omitted.contains("19700132"));
+        assert!(omitted.contains(r#""tag":"(0010,0030)""#));
+    }
+}
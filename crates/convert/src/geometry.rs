@@ -0,0 +1,332 @@
+use crate::errors::{CategorizedError, ErrorCategory};
+use dicom::core::Tag;
+use dicom::object::InMemDicomObject;
+use dicom::object::mem::InMemElement;
+use std::f64::consts::TAU;
+
+// Vertices of the polygons approximating circles and ellipses.
+const ARC_VERTICES: usize = 36;
+
+const GRAPHIC_DATA: Tag = Tag(0x0070, 0x0022);
+const GRAPHIC_TYPE: Tag = Tag(0x0070, 0x0023);
+const REFERENCED_TIME_OFFSETS: Tag = Tag(0x0040, 0xA138);
+
+type Point = Vec<f64>;
+
+fn string_value(dicom_object: &InMemDicomObject, tag: Tag) -> Option<String> {
+    dicom_object
+        .element(tag)
+        .ok()
+        .and_then(|e| e.string().ok())
+        .map(|s| s.trim().trim_end_matches('\0').to_string())
+}
+
+fn coordinates(point: &[f64]) -> String {
+    point
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn list(points: &[Point]) -> String {
+    points
+        .iter()
+        .map(|p| coordinates(p))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn ring(points: &[Point]) -> Vec<Point> {
+    let mut ring = points.to_vec();
+    if ring.first() != ring.last() {
+        ring.push(points[0].clone());
+    }
+    ring
+}
+
+fn difference(a: &[f64], b: &[f64]) -> Point {
+    a.iter().zip(b).map(|(a, b)| a - b).collect()
+}
+
+fn norm(v: &[f64]) -> f64 {
+    v.iter().map(|c| c * c).sum::<f64>().sqrt()
+}
+
+// Closed polygon around `center` with the semi-axes `u` and `v`, which are perpendicular.
+fn ellipse(center: &[f64], u: &[f64], v: &[f64]) -> Vec<Point> {
+    let mut points = (0..ARC_VERTICES)
+        .map(|i| {
+            let angle = TAU * i as f64 / ARC_VERTICES as f64;
+            center
+                .iter()
+                .zip(u.iter().zip(v))
+                .map(|(c, (u, v))| c + u * angle.cos() + v * angle.sin())
+                .collect()
+        })
+        .collect::<Vec<Point>>();
+    points.push(points[0].clone());
+    points
+}
+
+// A circle is given by its center and a point on its circumference, an ellipse by the end points
+// of its major and then its minor axis.
+fn arc(graphic_type: &str, points: &[Point]) -> Result<Vec<Point>, String> {
+    match (graphic_type, points) {
+        ("CIRCLE", [center, edge]) if center.len() == 2 => {
+            let radius = norm(&difference(edge, center));
+            Ok(ellipse(center, &[radius, 0.0], &[0.0, radius]))
+        }
+        ("ELLIPSE", [major_a, major_b, minor_a, minor_b]) => {
+            let center = major_a
+                .iter()
+                .zip(major_b)
+                .map(|(a, b)| (a + b) / 2.0)
+                .collect::<Point>();
+            let u = difference(major_b, &center);
+            let v = minor_b
+                .iter()
+                .zip(minor_a)
+                .map(|(b, a)| (b - a) / 2.0)
+                .collect::<Point>();
+            Ok(ellipse(&center, &u, &v))
+        }
+        _ => Err(format!(
+            "{} needs {} points, got {}",
+            graphic_type,
+            if graphic_type == "CIRCLE" { 2 } else { 4 },
+            points.len()
+        )),
+    }
+}
+
+// SCOORD coordinates are (column, row) pairs in the image pixel matrix, SCOORD3D coordinates are
+// (x, y, z) in the patient based coordinate system of the referenced Frame of Reference.
+fn spatial_wkt(
+    namespace: &str,
+    dicom_object: &InMemDicomObject,
+    values: &[f64],
+    three_d: bool,
+) -> Result<String, CategorizedError> {
+    let invalid = |message: String| CategorizedError::new(ErrorCategory::InvalidValue, message);
+    let Some(graphic_type) = string_value(dicom_object, GRAPHIC_TYPE) else {
+        return Err(invalid(String::from("Graphic Data without a Graphic Type")));
+    };
+    let dimension = if three_d { 3 } else { 2 };
+    if values.is_empty() || !values.len().is_multiple_of(dimension) {
+        return Err(invalid(format!(
+            "Graphic Data has {} values, which is not a multiple of {}",
+            values.len(),
+            dimension
+        )));
+    }
+    let points = values
+        .chunks(dimension)
+        .map(|c| c.to_vec())
+        .collect::<Vec<_>>();
+    let (crs, z) = if three_d {
+        ("patient", " Z")
+    } else {
+        ("image", "")
+    };
+    let geometry = match graphic_type.as_str() {
+        "POINT" if points.len() == 1 => format!("POINT{} ({})", z, coordinates(&points[0])),
+        "MULTIPOINT" => format!(
+            "MULTIPOINT{} ({})",
+            z,
+            points
+                .iter()
+                .map(|p| format!("({})", coordinates(p)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        // A polyline whose last point equals its first one outlines an area.
+        "POLYLINE" if points.len() > 3 && points.first() == points.last() => {
+            format!("POLYGON{} (({}))", z, list(&points))
+        }
+        "POLYLINE" if points.len() > 1 => format!("LINESTRING{} ({})", z, list(&points)),
+        "POLYGON" if points.len() > 2 => format!("POLYGON{} (({}))", z, list(&ring(&points))),
+        "CIRCLE" | "ELLIPSE" => format!(
+            "POLYGON{} (({}))",
+            z,
+            list(&arc(&graphic_type, &points).map_err(invalid)?)
+        ),
+        "POINT" | "POLYLINE" | "POLYGON" => {
+            return Err(invalid(format!(
+                "Cannot describe Graphic Type '{}' with {} points",
+                graphic_type,
+                points.len()
+            )));
+        }
+        // Ellipsoids are solids, which WKT cannot describe.
+        _ => {
+            return Err(CategorizedError::new(
+                ErrorCategory::UnsupportedGeometry,
+                format!("Unsupported Graphic Type '{}'", graphic_type),
+            ));
+        }
+    };
+    Ok(format!("<{}crs/{}> {}", namespace, crs, geometry))
+}
+
+// TCOORD time offsets in seconds become xsd:duration values, e.g. -PT1.5S.
+fn duration(seconds: f64) -> Result<String, String> {
+    if !seconds.is_finite() {
+        return Err(format!("Invalid time offset {}", seconds));
+    }
+    let sign = if seconds < 0.0 { "-" } else { "" };
+    Ok(format!("{}PT{}S", sign, seconds.abs()))
+}
+
+// The Referenced Time Offsets of a TCOORD content item as xsd:duration lexical forms, for the data
+// element holding them.
+pub fn time_offsets(
+    dicom_object: &InMemDicomObject,
+    data_element: &InMemElement,
+) -> Result<Vec<String>, String> {
+    let value_type = string_value(dicom_object, Tag(0x0040, 0xA040));
+    if value_type.as_deref() != Some("TCOORD")
+        || data_element.header().tag != REFERENCED_TIME_OFFSETS
+    {
+        return Ok(Vec::new());
+    }
+    data_element
+        .to_multi_float64()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(duration)
+        .collect()
+}
+
+// The geometry of a SCOORD or SCOORD3D content item as a GeoSPARQL WKT literal, for the data
+// element holding its coordinates. Its coordinate reference system is named in `namespace`.
+pub fn wkt(
+    namespace: &str,
+    dicom_object: &InMemDicomObject,
+    data_element: &InMemElement,
+) -> Result<Option<String>, CategorizedError> {
+    let value_type = string_value(dicom_object, Tag(0x0040, 0xA040));
+    let tag = data_element.header().tag;
+    let three_d = match (value_type.as_deref(), tag) {
+        (Some("SCOORD"), GRAPHIC_DATA) => false,
+        (Some("SCOORD3D"), GRAPHIC_DATA) => true,
+        _ => return Ok(None),
+    };
+    // Single precision values go through their shortest representation, so that 0.1 is not
+    // written as 0.10000000149011612.
+    let values = match data_element.value().float32_slice() {
+        Ok(values) => values
+            .iter()
+            .map(|v| v.to_string().parse().unwrap_or(f64::NAN))
+            .collect(),
+        Err(_) => data_element
+            .to_multi_float64()
+            .map_err(|e| CategorizedError::new(ErrorCategory::InvalidValue, e))?,
+    };
+    spatial_wkt(namespace, dicom_object, &values, three_d).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dicom::core::{DataElement, PrimitiveValue, VR};
+
+    const NAMESPACE: &str = "http://dicom2rdf.uniklinik-freiburg.de/";
+
+    fn item(value_type: &str, graphic_type: &str) -> InMemDicomObject {
+        InMemDicomObject::from_element_iter([
+            DataElement::new(
+                Tag(0x0040, 0xA040),
+                VR::CS,
+                PrimitiveValue::from(value_type),
+            ),
+            DataElement::new(GRAPHIC_TYPE, VR::CS, PrimitiveValue::from(graphic_type)),
+        ])
+    }
+
+    fn scoord(value_type: &str, graphic_type: &str, values: &[f32]) -> Result<String, String> {
+        let data_element = DataElement::new(
+            GRAPHIC_DATA,
+            VR::FL,
+            PrimitiveValue::F32(values.iter().copied().collect()),
+        );
+        wkt(NAMESPACE, &item(value_type, graphic_type), &data_element)
+            .map(|wkt| wkt.unwrap())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn points_and_lines() {
+        assert_eq!(
+            scoord("SCOORD", "POINT", &[1.5, 0.1]).unwrap(),
+            "<http://dicom2rdf.uniklinik-freiburg.de/crs/image> POINT (1.5 0.1)"
+        );
+        assert_eq!(
+            scoord("SCOORD3D", "MULTIPOINT", &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap(),
+            "<http://dicom2rdf.uniklinik-freiburg.de/crs/patient> MULTIPOINT Z ((1 2 3), (4 5 6))"
+        );
+        assert_eq!(
+            scoord("SCOORD", "POLYLINE", &[0.0, 0.0, 1.0, 1.0]).unwrap(),
+            "<http://dicom2rdf.uniklinik-freiburg.de/crs/image> LINESTRING (0 0, 1 1)"
+        );
+    }
+
+    #[test]
+    fn areas_are_closed_polygons() {
+        let triangle = [0.0, 0.0, 1.0, 0.0, 0.0, 1.0];
+        let closed =
+            "<http://dicom2rdf.uniklinik-freiburg.de/crs/image> POLYGON ((0 0, 1 0, 0 1, 0 0))";
+        assert_eq!(scoord("SCOORD", "POLYGON", &triangle).unwrap(), closed);
+        let mut polyline = triangle.to_vec();
+        polyline.extend([0.0, 0.0]);
+        assert_eq!(scoord("SCOORD", "POLYLINE", &polyline).unwrap(), closed);
+    }
+
+    #[test]
+    fn circles_are_approximated() {
+        let wkt = scoord("SCOORD", "CIRCLE", &[10.0, 10.0, 12.0, 10.0]).unwrap();
+        let ring = wkt
+            .strip_prefix("<http://dicom2rdf.uniklinik-freiburg.de/crs/image> POLYGON ((")
+            .and_then(|wkt| wkt.strip_suffix("))"))
+            .unwrap()
+            .split(", ")
+            .collect::<Vec<_>>();
+        assert_eq!(ring.len(), ARC_VERTICES + 1);
+        assert_eq!(ring.first(), Some(&"12 10"));
+        assert_eq!(ring.first(), ring.last());
+    }
+
+    #[test]
+    fn unsupported_and_malformed_coordinates() {
+        let data_element = DataElement::new(
+            GRAPHIC_DATA,
+            VR::FL,
+            PrimitiveValue::F32([0.0, 0.0, 0.0, 1.0, 1.0, 1.0].into_iter().collect()),
+        );
+        let error = wkt(NAMESPACE, &item("SCOORD3D", "ELLIPSOID"), &data_element).unwrap_err();
+        assert_eq!(error.category, ErrorCategory::UnsupportedGeometry);
+        let error = wkt(NAMESPACE, &item("SCOORD", "POINT"), &data_element).unwrap_err();
+        assert_eq!(error.category, ErrorCategory::InvalidValue);
+        assert!(scoord("SCOORD", "POLYGON", &[0.0, 0.0, 1.0]).is_err());
+        assert!(scoord("SCOORD", "CIRCLE", &[0.0, 0.0]).is_err());
+    }
+
+    #[test]
+    fn time_offsets_are_durations() {
+        let data_element = DataElement::new(
+            REFERENCED_TIME_OFFSETS,
+            VR::FD,
+            PrimitiveValue::F64([0.0, 1.5, -0.25, 60.0].into_iter().collect()),
+        );
+        let tcoord = InMemDicomObject::from_element_iter([DataElement::new(
+            Tag(0x0040, 0xA040),
+            VR::CS,
+            PrimitiveValue::from("TCOORD"),
+        )]);
+        assert_eq!(
+            time_offsets(&tcoord, &data_element).unwrap(),
+            ["PT0S", "PT1.5S", "-PT0.25S", "PT60S"]
+        );
+        assert!(duration(f64::INFINITY).is_err());
+    }
+}
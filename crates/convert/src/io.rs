@@ -1,3 +1,4 @@
+use config::Config;
 use flate2::{Compression, write::GzEncoder};
 use std::{
     error::Error,
@@ -40,34 +41,69 @@ impl<W: Write> Write for TripleWriter<W> {
 }
 
 pub fn writer<P: AsRef<Path>>(output_dir: P, file_name: &str) -> BufWriter<File> {
-    fs::create_dir_all(&output_dir).expect(&format!(
-        "Failed to create output directory '{}'",
-        output_dir.as_ref().display()
-    ));
+    fs::create_dir_all(&output_dir).unwrap_or_else(|_| {
+        panic!(
+            "Failed to create output directory '{}'",
+            output_dir.as_ref().display()
+        )
+    });
     let file_path = output_dir.as_ref().join(file_name);
-    let file = File::create(&file_path).expect(&format!(
-        "Failed to create writer file '{}' in '{:?}'",
-        file_name,
-        output_dir.as_ref()
-    ));
+    let file = File::create(&file_path).unwrap_or_else(|_| {
+        panic!(
+            "Failed to create writer file '{}' in '{:?}'",
+            file_name,
+            output_dir.as_ref()
+        )
+    });
     BufWriter::new(file)
 }
 
-pub fn ttl_gz_writer<P: AsRef<Path>>(output_dir: P, file_name: &str) -> GzEncoder<BufWriter<File>> {
+// Prefixes used by the raw triples, besides the dicom2rdf namespace of the config.
+const STANDARD_PREFIXES: [(&str, &str); 5] = [
+    ("geo", "http://www.opengis.net/ont/geosparql#"),
+    ("prov", "http://www.w3.org/ns/prov#"),
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+];
+
+// The namespace of the raw triples, which their node IRIs are derived from as well.
+pub fn raw_namespace(config: &Config) -> Result<&str, String> {
+    config
+        .prefix_iri("dicom2rdf")
+        .ok_or_else(|| String::from("The config declares no 'dicom2rdf' prefix"))
+}
+
+pub fn raw_prefixes(namespace: &str) -> Vec<(&str, &str)> {
+    std::iter::once(("dicom2rdf", namespace))
+        .chain(STANDARD_PREFIXES)
+        .collect()
+}
+
+pub fn ttl_gz_writer<P: AsRef<Path>>(
+    output_dir: P,
+    file_name: &str,
+    namespace: &str,
+) -> GzEncoder<BufWriter<File>> {
+    prefixed_ttl_gz_writer(output_dir, file_name, raw_prefixes(namespace))
+}
+
+pub fn prefixed_ttl_gz_writer<'a, P: AsRef<Path>>(
+    output_dir: P,
+    file_name: &str,
+    prefixes: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> GzEncoder<BufWriter<File>> {
     let writer = writer(output_dir.as_ref(), file_name);
     let mut encoder = GzEncoder::new(writer, Compression::fast());
     Write::write_all(
         &mut encoder,
-        [
-            "@prefix dicom2rdf: <http://dicom2rdf.uniklinik-freiburg.de/> .",
-            "@prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .",
-            "@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .",
-            "@prefix xsd: <http://www.w3.org/2001/XMLSchema#> .",
-        ]
-        .join("\n")
-        .as_bytes(),
+        prefixes
+            .into_iter()
+            .map(|(prefix, iri)| format!("@prefix {}: <{}> .\n", prefix, iri))
+            .collect::<String>()
+            .as_bytes(),
     )
-    .expect("Failed to write well-known prefixes");
+    .expect("Failed to write prefixes");
     encoder
 }
 
@@ -76,7 +112,7 @@ pub fn handle_zst_file<P: AsRef<Path>>(
 ) -> Result<(PathBuf, Option<TempDir>), Box<dyn Error>> {
     let path = path.as_ref();
     let temp_dir = tempdir()?;
-    let tar_zst_file = File::open(&path)?;
+    let tar_zst_file = File::open(path)?;
     let decoder = zstd::stream::read::Decoder::new(tar_zst_file)?;
     let mut archive = Archive::new(decoder);
     archive.unpack(temp_dir.path())?;
@@ -96,10 +132,10 @@ fn is_dcm_or_zst(de: &walkdir::DirEntry) -> bool {
     if !de.file_type().is_file() {
         return false;
     };
-    match de.path().extension().and_then(|s| s.to_str()) {
-        Some("dcm") | Some("zst") => true,
-        _ => false,
-    }
+    matches!(
+        de.path().extension().and_then(|s| s.to_str()),
+        Some("dcm") | Some("zst")
+    )
 }
 
 pub fn get_dcm_or_zst_paths<P: AsRef<Path>>(root: P) -> impl Iterator<Item = PathBuf> {
@@ -1,5 +1,15 @@
+pub mod bulk;
+pub mod charset;
 pub mod datetime;
+pub mod deidentification;
 pub mod dicom;
+pub mod encapsulated;
+pub mod errors;
+pub mod geometry;
 pub mod io;
+pub mod numeric;
 pub mod progress;
+pub mod provenance;
+pub mod scrub;
+pub mod semantic;
 pub mod turtle;
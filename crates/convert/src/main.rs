@@ -2,16 +2,30 @@
 static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
 use clap::Parser;
-use config::Config;
-use convert::dicom::write_triples;
-use convert::io::{TripleWriter, get_dcm_or_zst_paths, handle_zst_file, ttl_gz_writer, writer};
+use config::deidentification::ProfileAction;
+use config::{Config, DocumentIdentity};
+use convert::charset::{CharacterSet, RawTexts};
+use convert::deidentification::{Report, replacement_uid};
+use convert::dicom::{DateShift, Document, Node, write_triples};
+use convert::errors::{ConversionError, ErrorCategory, ErrorSummary, ErrorWriter, truncated_value};
+use convert::io::{
+    TripleWriter, get_dcm_or_zst_paths, handle_zst_file, prefixed_ttl_gz_writer, raw_namespace,
+    ttl_gz_writer, writer,
+};
 use convert::progress::progress_logger;
-use convert::turtle;
-use dicom::object::open_file;
+use convert::provenance::{Converter, Source, write_provenance};
+use convert::scrub::Scrubber;
+use convert::semantic::{DocumentTriples, SemanticMapping, SemanticWriter};
+use convert::turtle::{self, TripleSink};
+use dicom::core::{DataElement, Tag};
+use dicom::object::{FileMetaTable, InMemDicomObject, from_reader};
 use log::{info, warn};
 use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 fn dir_exists(s: &str) -> Result<PathBuf, String> {
@@ -37,50 +51,295 @@ struct Args {
     /// Directory where the output is written to
     #[arg(long, required = true, value_parser = dir_exists)]
     output_dir: PathBuf,
+
+    /// Directory where bulk data (OB, OD, OF, OL, OV, OW, UN and pixel data) is stored by SHA-256.
+    /// Bulk data is stored as is, so this is refused when the config enables de-identification
+    #[arg(long)]
+    bulk_data_dir: Option<PathBuf>,
+
+    /// Also write semantic triples, without going through a raw index and the construct queries
+    #[arg(long)]
+    semantic: bool,
+}
+
+fn sha256_hex(bytes: impl AsRef<[u8]>) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+fn sop_instance_uid(dicom_object: &InMemDicomObject) -> Option<&str> {
+    dicom_object
+        .element(Tag(0x0008, 0x0018))
+        .ok()
+        .and_then(|e| e.string().ok())
+        .map(|uid| uid.trim().trim_end_matches('\0'))
+        .filter(|uid| !uid.is_empty())
+}
+
+// File Meta Information as a dataset, without the group length. Application entity titles and
+// private information identify sites and devices, so they are left out when de-identifying.
+fn file_meta_object(meta: &FileMetaTable, config: &Config) -> InMemDicomObject {
+    InMemDicomObject::from_element_iter(
+        meta.to_element_iter()
+            .filter(|e| e.header().tag.element() != 0x0000)
+            .filter(|e| {
+                config.deidentification.is_none()
+                    || !matches!(
+                        e.header().tag.element(),
+                        0x0016 | 0x0017 | 0x0018 | 0x0100 | 0x0102
+                    )
+            })
+            .filter_map(|e| {
+                let (header, value) = e.into_parts();
+                Some(DataElement::new(
+                    header.tag,
+                    header.vr,
+                    value.into_primitive()?,
+                ))
+            }),
+    )
+}
+
+// Hashes of the source path or content would link de-identified documents back to their files,
+// so they are keyed when de-identifying.
+fn source_hash(config: &Config, digest: String) -> String {
+    match (&config.deidentification, &config.pseudonymization) {
+        (Some(_), Some(pseudonymization)) => pseudonymization.hash(&digest),
+        _ => digest,
+    }
+}
+
+fn path_key(config: &Config, source_path: &str) -> String {
+    source_hash(config, sha256_hex(source_path))
+}
+
+// File paths may identify the patient, so errors of de-identified or pseudonymized documents
+// refer to the document key instead.
+fn hides_file_paths(config: &Config) -> bool {
+    config.deidentification.is_some() || config.pseudonymization.is_some()
+}
+
+fn document_key(
+    error_writer: &mut ErrorWriter<impl Write>,
+    dicom_object: &InMemDicomObject,
+    file_path: &str,
+    file_sha256: &str,
+    source_path: &str,
+    config: &Config,
+) -> std::io::Result<String> {
+    Ok(match config.document_identity {
+        DocumentIdentity::SopInstanceUid => match sop_instance_uid(dicom_object) {
+            Some(uid) if config.tag_action(Tag(0x0008, 0x0018), None) == Some(ProfileAction::K) => {
+                uid.to_string()
+            }
+            Some(uid) => replacement_uid(config, uid).unwrap_or_else(|| uid.to_string()),
+            None => {
+                let key = path_key(config, source_path);
+                error_writer.log(&ConversionError::file(
+                    ErrorCategory::MissingSopInstanceUid,
+                    if hides_file_paths(config) {
+                        &key
+                    } else {
+                        file_path
+                    },
+                    "Missing SOP Instance UID, identifying document by path hash",
+                ))?;
+                key
+            }
+        },
+        DocumentIdentity::PathHash => path_key(config, source_path),
+        DocumentIdentity::ContentHash => source_hash(config, file_sha256.to_string()),
+    })
+}
+
+fn redecode_texts(
+    error_writer: &mut ErrorWriter<impl Write>,
+    dicom_object: &mut InMemDicomObject,
+    charset: &CharacterSet,
+    file_bytes: &[u8],
+    file_path: &str,
+) -> std::io::Result<()> {
+    let raw = match RawTexts::read(file_bytes) {
+        Ok(raw) => raw,
+        Err(e) => {
+            return error_writer.log(&ConversionError::file(
+                ErrorCategory::CharsetDecoding,
+                file_path,
+                format!("Failed to read raw text values: {}", e),
+            ));
+        }
+    };
+    let mut errors = Vec::new();
+    charset.redecode(dicom_object, &raw, "", &mut errors);
+    for e in errors {
+        error_writer.log(&ConversionError {
+            category: ErrorCategory::CharsetDecoding,
+            file: file_path,
+            tag: Some(e.tag),
+            tag_path: Some(&e.tag_path),
+            vr: Some(e.vr.to_string()),
+            value: Some(truncated_value(&e.lossy)),
+            message: e.message,
+        })?;
+    }
+    Ok(())
+}
+
+// Inputs shared by all files of a run.
+struct Conversion<'a> {
+    input_dir: &'a Path,
+    config: &'a Config,
+    namespace: &'a str,
+    bulk_data_dir: Option<&'a Path>,
+    converter: Converter<'a>,
+    semantic: Option<SemanticMapping>,
+    report: Mutex<Report>,
+    errors: Mutex<ErrorSummary>,
 }
 
 fn convert_file<P: AsRef<Path>>(
     triple_writer: &mut TripleWriter<impl Write>,
-    error_writer: &mut impl Write,
+    error_writer: &mut ErrorWriter<impl Write>,
+    semantic_writer: Option<&mut SemanticWriter<impl Write>>,
     path: P,
-    config: &Config,
+    conversion: &Conversion,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut buffer = Vec::new();
-    let (dicom_file_path, _temp_dir_guard) =
-        if path.as_ref().extension().and_then(|s| s.to_str()) == Some("zst") {
-            handle_zst_file(&path)?
-        } else {
-            (path.as_ref().to_path_buf(), None)
-        };
+    let config = conversion.config;
+    let mut buffer = DocumentTriples::new(conversion.semantic.is_some());
+    let is_archive = path.as_ref().extension().and_then(|s| s.to_str()) == Some("zst");
+    let (dicom_file_path, _temp_dir_guard) = if is_archive {
+        handle_zst_file(&path)?
+    } else {
+        (path.as_ref().to_path_buf(), None)
+    };
 
     let file_name = dicom_file_path
         .file_name()
         .and_then(|s| s.to_str())
         .ok_or("Failed to get DICOM file name")?;
-    let dicom_object = open_file(&dicom_file_path)?;
-    let file_subject = turtle::IRI::prefix("dicom2rdf", file_name);
-    writeln!(
+    let source_path = path
+        .as_ref()
+        .strip_prefix(conversion.input_dir)
+        .unwrap_or(path.as_ref())
+        .to_string_lossy();
+    let file_path = if is_archive {
+        format!("{}/{}", source_path, file_name)
+    } else {
+        source_path.to_string()
+    };
+    let file_bytes = std::fs::read(&dicom_file_path)?;
+    let file_sha256 = sha256_hex(&file_bytes);
+    let file_metadata = std::fs::metadata(&dicom_file_path)?;
+    let mut dicom_object = from_reader(file_bytes.as_slice())?;
+    let key = document_key(
+        error_writer,
+        &dicom_object,
+        &file_path,
+        &file_sha256,
+        &source_path,
+        config,
+    )?;
+    let error_file = if hides_file_paths(config) {
+        key.as_str()
+    } else {
+        file_path.as_str()
+    };
+    let file_subject = turtle::IRI::prefix("dicom2rdf", &key);
+    let root_path = urlencoding::encode(&key);
+    buffer.write_triple(turtle::triple(
+        &file_subject,
+        &turtle::IRI::prefix("rdf", "type"),
+        &turtle::TripleObject::from(turtle::IRI::prefix("dicom2rdf", "DocumentRoot")),
+    ))?;
+    let deidentified = config.deidentification.is_some();
+    write_provenance(
         &mut buffer,
-        "{}",
-        turtle::triple(
-            &file_subject,
-            &turtle::IRI::prefix("rdf", "type"),
-            &turtle::TripleObject::from(turtle::IRI::prefix("dicom2rdf", "DocumentRoot")),
-        )
+        conversion.namespace,
+        &file_subject,
+        &root_path,
+        &Source {
+            path: Some(source_path.as_ref()).filter(|_| !deidentified),
+            archive_member: Some(file_name).filter(|_| is_archive && !deidentified),
+            byte_length: file_bytes.len() as u64,
+            sha256: Some(file_sha256.as_str()).filter(|_| !deidentified),
+            modified: file_metadata.modified().ok().filter(|_| !deidentified),
+        },
+        &conversion.converter,
     )?;
 
-    let (_, max_depth) = write_triples(
-        &mut buffer,
+    let charset = CharacterSet::from_dataset(&dicom_object);
+    for term in charset.unsupported_terms() {
+        error_writer.log(&ConversionError::file(
+            ErrorCategory::UnsupportedCharset,
+            error_file,
+            format!("Unsupported Specific Character Set '{}'", term),
+        ))?;
+    }
+    redecode_texts(
         error_writer,
-        &file_subject,
-        &dicom_object,
-        &file_name,
-        &config,
-        0,
-    );
+        &mut dicom_object,
+        &charset,
+        &file_bytes,
+        error_file,
+    )?;
+    let patient_id = dicom_object
+        .element(Tag(0x0010, 0x0020))
+        .ok()
+        .and_then(|e| e.string().ok())
+        .map(|id| id.trim().trim_end_matches('\0'))
+        .filter(|id| !id.is_empty());
+    let date_shift = match (&config.pseudonymization, patient_id) {
+        (Some(pseudonymization), Some(patient_id)) => pseudonymization
+            .date_shift_days(patient_id)
+            .map(DateShift::Days),
+        (Some(pseudonymization), None) if pseudonymization.shifts_dates() => {
+            error_writer.log(&ConversionError::file(
+                ErrorCategory::MissingPatientId,
+                error_file,
+                "Missing Patient ID, removing dates instead of shifting them",
+            ))?;
+            Some(DateShift::Remove)
+        }
+        _ => None,
+    };
+    let scrubber = config
+        .scrubbing
+        .as_ref()
+        .map(|scrubbing| Scrubber::new(scrubbing, &dicom_object));
+    let file_meta = file_meta_object(dicom_object.meta(), config);
+    let root = Node {
+        subject: &file_subject,
+        path: &root_path,
+        tag_path: "",
+        depth: 0,
+        language: config.language.as_deref(),
+        template: None,
+        redaction: None,
+    };
+    let document = Document {
+        file_path: error_file,
+        config,
+        namespace: conversion.namespace,
+        report: RefCell::default(),
+        date_shift,
+        scrubber,
+        transfer_syntax: dicom_object.meta().transfer_syntax(),
+        bulk_data_dir: conversion.bulk_data_dir,
+        dataset: &dicom_object,
+        root: &root,
+    };
+    write_triples(&mut buffer, error_writer, &root, &file_meta, &document);
+    let max_depth = write_triples(&mut buffer, error_writer, &root, &dicom_object, &document);
+    conversion
+        .report
+        .lock()
+        .expect("Report lock poisoned")
+        .merge(document.report.into_inner());
     triple_writer.max_depth = triple_writer.max_depth.max(max_depth);
-    if !buffer.is_empty() {
-        triple_writer.write_all(&buffer)?;
+    if let (Some(semantic), Some(semantic_writer)) = (&conversion.semantic, semantic_writer) {
+        semantic.write_document(semantic_writer, &buffer)?;
+    }
+    if !buffer.turtle.is_empty() {
+        triple_writer.write_all(&buffer.turtle)?;
     }
     Ok(())
 }
@@ -102,10 +361,34 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
     let args = Args::parse();
     let config = Config::load_from_file(&args.config)?;
+    if args.bulk_data_dir.is_some() && config.deidentification.is_some() {
+        return Err(
+            "--bulk-data-dir stores bulk data as is and cannot be used with [deidentification]"
+                .into(),
+        );
+    }
 
     clear_output_dir(&args.output_dir)?;
 
     info!("\x1b[1mStarting conversion of DICOM SR to raw RDF Turtle\x1b[0m");
+    let config_sha256 = sha256_hex(std::fs::read(&args.config)?);
+    let conversion = Conversion {
+        input_dir: &args.input_dir,
+        config: &config,
+        namespace: raw_namespace(&config)?,
+        bulk_data_dir: args.bulk_data_dir.as_deref(),
+        converter: Converter {
+            version: env!("CARGO_PKG_VERSION"),
+            config_sha256: &config_sha256,
+        },
+        semantic: if args.semantic {
+            Some(SemanticMapping::new(&config)?)
+        } else {
+            None
+        },
+        report: Mutex::new(Report::default()),
+        errors: Mutex::new(ErrorSummary::default()),
+    };
     let worker_id = AtomicUsize::new(0);
     let (progress_sender, progress_logger_thread) = progress_logger();
 
@@ -113,18 +396,53 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .par_bridge()
         .for_each_init(
             || {
-                let worker_name =
-                    format!("raw-dicom-{:03}", worker_id.fetch_add(1, Ordering::Relaxed));
+                let worker_suffix = format!("{:03}", worker_id.fetch_add(1, Ordering::Relaxed));
+                let worker_name = format!("raw-dicom-{}", worker_suffix);
                 let triple_writer = TripleWriter::new(ttl_gz_writer(
                     &args.output_dir,
                     &format!("{}.ttl.gz", worker_name),
+                    conversion.namespace,
                 ));
-                let error_writer = writer(&args.output_dir, &format!("{}-errors.log", worker_name));
-                (triple_writer, error_writer, progress_sender.clone())
+                let semantic_writer = conversion.semantic.as_ref().map(|semantic| {
+                    SemanticWriter::new(prefixed_ttl_gz_writer(
+                        &args.output_dir,
+                        &format!("semantic-dicom-{}.ttl.gz", worker_suffix),
+                        semantic.prefixes(),
+                    ))
+                });
+                let error_writer = ErrorWriter::new(
+                    writer(&args.output_dir, &format!("{}-errors.jsonl", worker_name)),
+                    &conversion.errors,
+                    hides_file_paths(&config),
+                );
+                (
+                    triple_writer,
+                    error_writer,
+                    semantic_writer,
+                    progress_sender.clone(),
+                )
             },
-            |(triple_writer, error_writer, progress_sender), path| {
-                if let Err(e) = convert_file(triple_writer, error_writer, &path, &config) {
-                    warn!("Failed to convert file {:?}: {}", path, e)
+            |(triple_writer, error_writer, semantic_writer, progress_sender), path| {
+                if let Err(e) = convert_file(
+                    triple_writer,
+                    error_writer,
+                    semantic_writer.as_mut(),
+                    &path,
+                    &conversion,
+                ) {
+                    warn!("Failed to convert file {:?}: {}", path, e);
+                    let source_path = path.strip_prefix(&args.input_dir).unwrap_or(&path);
+                    let source_path = source_path.to_string_lossy();
+                    let file = if hides_file_paths(&config) {
+                        path_key(&config, &source_path)
+                    } else {
+                        source_path.into_owned()
+                    };
+                    let _ = error_writer.log(&ConversionError::file(
+                        ErrorCategory::UnreadableFile,
+                        &file,
+                        e,
+                    ));
                 }
                 progress_sender.tick();
             },
@@ -132,5 +450,40 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     drop(progress_sender);
     progress_logger_thread.join().expect("Thread panicked");
 
+    let errors = conversion
+        .errors
+        .into_inner()
+        .expect("Error summary lock poisoned");
+    for (category, count) in errors.totals() {
+        warn!("Conversion errors {}: {}", category, count);
+    }
+    let mut summary_writer = writer(&args.output_dir, "conversion-errors.tsv");
+    errors.write_tsv(&mut summary_writer)?;
+    summary_writer.flush()?;
+
+    let report = conversion
+        .report
+        .into_inner()
+        .expect("Report lock poisoned");
+    if config.deidentification.is_some() {
+        for (action, count) in report.totals() {
+            info!(
+                "De-identification action {}: {} data elements",
+                action, count
+            );
+        }
+        let mut report_writer = writer(&args.output_dir, "deidentification-report.tsv");
+        report.write_tsv(&mut report_writer)?;
+        report_writer.flush()?;
+    }
+    if config.scrubbing.is_some() {
+        for (label, count) in &report.scrubbed {
+            info!("Scrubbed free text: {} [{}] replacements", count, label);
+        }
+        let mut report_writer = writer(&args.output_dir, "scrubbing-report.tsv");
+        report.write_scrubbing_tsv(&mut report_writer)?;
+        report_writer.flush()?;
+    }
+
     Ok(())
 }
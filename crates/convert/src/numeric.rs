@@ -0,0 +1,134 @@
+use crate::turtle;
+use regex::Regex;
+use std::sync::LazyLock;
+
+// Fixed point or ANSI X3.9 floating point numbers, the same grammar as the xsd:double lexical
+// space without its special values.
+static DECIMAL_STRING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[+-]?([0-9]+(\.[0-9]*)?|\.[0-9]+)([eE][+-]?[0-9]+)?$").unwrap());
+
+// The canonical xsd:double lexical form: a mantissa with a single digit before and at least one
+// digit after the point, e.g. 1.2345E1. The shortest mantissa that round-trips to the original
+// binary value is used, so 12.345 as f32 does not become 1.2345000267028809E1.
+fn double_lexical(value: impl std::fmt::UpperExp) -> String {
+    let exponential = format!("{:E}", value);
+    match exponential.split_once('E') {
+        Some((mantissa, exponent)) if !mantissa.contains('.') => {
+            format!("{}.0E{}", mantissa, exponent)
+        }
+        _ => exponential,
+    }
+}
+
+pub fn float_to_typed_literal<F: Into<f64> + std::fmt::UpperExp + Copy>(
+    x: F,
+) -> turtle::TypedLiteral {
+    let value: f64 = x.into();
+    let lexical = if value.is_nan() {
+        String::from("NaN")
+    } else if value.is_infinite() {
+        String::from(if value > 0.0 { "INF" } else { "-INF" })
+    } else {
+        double_lexical(x)
+    };
+    turtle::TypedLiteral::new(lexical, turtle::IRI::prefix("xsd", "double"))
+}
+
+pub fn decimal_string_to_typed_literal(ds: &str) -> Result<turtle::TypedLiteral, String> {
+    if !DECIMAL_STRING.is_match(ds) {
+        return Err(format!("Invalid decimal string: '{}'", ds));
+    }
+    if ds.contains(['e', 'E']) {
+        // Exponent notation is not allowed in xsd:decimal, but is a valid xsd:double.
+        return Ok(turtle::TypedLiteral::new(
+            ds,
+            turtle::IRI::prefix("xsd", "double"),
+        ));
+    }
+    let (sign, digits) = match ds.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", ds.strip_prefix('+').unwrap_or(ds)),
+    };
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let lexical = format!(
+        "{}{}{}",
+        sign,
+        if integer.is_empty() { "0" } else { integer },
+        if fraction.is_empty() {
+            String::new()
+        } else {
+            format!(".{}", fraction)
+        }
+    );
+    Ok(turtle::TypedLiteral::new(
+        lexical,
+        turtle::IRI::prefix("xsd", "decimal"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ds(s: &str) -> Result<String, String> {
+        decimal_string_to_typed_literal(s).map(|literal| literal.to_string())
+    }
+
+    #[test]
+    fn decimal_strings_are_exact_decimals() {
+        assert_eq!(ds("12.345").unwrap(), "\"12.345\"^^xsd:decimal");
+        assert_eq!(ds("+.5").unwrap(), "\"0.5\"^^xsd:decimal");
+        assert_eq!(ds("-7.").unwrap(), "\"-7\"^^xsd:decimal");
+        assert_eq!(ds("1.5e-3").unwrap(), "\"1.5e-3\"^^xsd:double");
+    }
+
+    #[test]
+    fn special_values_are_not_decimal_strings() {
+        for s in [
+            "inf",
+            "-infinity",
+            "NaN",
+            "INF",
+            "1e",
+            ".",
+            "",
+            "1,5",
+            "0x10",
+        ] {
+            assert!(ds(s).is_err(), "{:?} was accepted", s);
+        }
+    }
+
+    #[test]
+    fn floats_use_the_canonical_double_form() {
+        let double = |literal: turtle::TypedLiteral| literal.to_string();
+        assert_eq!(
+            double(float_to_typed_literal(12.345f32)),
+            "\"1.2345E1\"^^xsd:double"
+        );
+        assert_eq!(
+            double(float_to_typed_literal(1.0f64)),
+            "\"1.0E0\"^^xsd:double"
+        );
+        assert_eq!(
+            double(float_to_typed_literal(0.0f64)),
+            "\"0.0E0\"^^xsd:double"
+        );
+        assert_eq!(
+            double(float_to_typed_literal(-0.0f64)),
+            "\"-0.0E0\"^^xsd:double"
+        );
+        assert_eq!(
+            double(float_to_typed_literal(1e-300f64)),
+            "\"1.0E-300\"^^xsd:double"
+        );
+        assert_eq!(
+            double(float_to_typed_literal(f64::NAN)),
+            "\"NaN\"^^xsd:double"
+        );
+        assert_eq!(
+            double(float_to_typed_literal(f32::NEG_INFINITY)),
+            "\"-INF\"^^xsd:double"
+        );
+    }
+}
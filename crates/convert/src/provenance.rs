@@ -0,0 +1,155 @@
+use crate::turtle::{self, TripleSink};
+use dicom::core::chrono::{DateTime, SecondsFormat, Utc};
+use std::sync::LazyLock;
+use std::time::SystemTime;
+
+static RDF_TYPE_IRI: LazyLock<turtle::IRI> = LazyLock::new(|| turtle::IRI::prefix("rdf", "type"));
+
+// The input file a document was converted from. Path, archive member, hash and modification
+// time are left out of de-identified graphs, since they may identify the patient or link the
+// graph back to the file.
+pub struct Source<'a> {
+    pub path: Option<&'a str>,
+    pub archive_member: Option<&'a str>,
+    pub byte_length: u64,
+    pub sha256: Option<&'a str>,
+    pub modified: Option<SystemTime>,
+}
+
+pub struct Converter<'a> {
+    pub version: &'a str,
+    pub config_sha256: &'a str,
+}
+
+fn date_time_literal(date_time: DateTime<Utc>) -> turtle::TripleObject {
+    turtle::TripleObject::from(turtle::TypedLiteral::new(
+        date_time.to_rfc3339_opts(SecondsFormat::Secs, true),
+        turtle::IRI::prefix("xsd", "dateTime"),
+    ))
+}
+
+fn string_literal(s: &str) -> turtle::TripleObject {
+    turtle::TripleObject::from(turtle::PlainLiteral::String(s.to_string()))
+}
+
+// A document root is a prov:Entity derived from its source file by a conversion activity, which
+// used the config and is associated with the converter build.
+pub fn write_provenance(
+    writer: &mut impl TripleSink,
+    namespace: &str,
+    document_root: &turtle::IRI,
+    path: &str,
+    source: &Source,
+    converter: &Converter,
+) -> std::io::Result<()> {
+    let source_node = turtle::skolem_iri(namespace, &format!("{}/source", path));
+    let activity = turtle::skolem_iri(namespace, &format!("{}/conversion", path));
+    let agent = turtle::IRI::prefix_path("dicom2rdf", &["convert", converter.version]);
+    let config = turtle::IRI::prefix_path("dicom2rdf", &["config", converter.config_sha256]);
+    let now = Utc::now();
+    let mut triples = vec![
+        (
+            document_root,
+            turtle::IRI::prefix("prov", "wasDerivedFrom"),
+            turtle::TripleObject::from(source_node.clone()),
+        ),
+        (
+            document_root,
+            turtle::IRI::prefix("prov", "wasGeneratedBy"),
+            turtle::TripleObject::from(activity.clone()),
+        ),
+        (
+            document_root,
+            turtle::IRI::prefix("prov", "generatedAtTime"),
+            date_time_literal(now),
+        ),
+        (
+            &source_node,
+            RDF_TYPE_IRI.clone(),
+            turtle::TripleObject::from(turtle::IRI::prefix("prov", "Entity")),
+        ),
+        (
+            &source_node,
+            turtle::IRI::prefix("dicom2rdf", "byte_length"),
+            turtle::TripleObject::from(turtle::PlainLiteral::Integer(source.byte_length as i64)),
+        ),
+        (
+            &activity,
+            RDF_TYPE_IRI.clone(),
+            turtle::TripleObject::from(turtle::IRI::prefix("prov", "Activity")),
+        ),
+        (
+            &activity,
+            turtle::IRI::prefix("prov", "used"),
+            turtle::TripleObject::from(source_node.clone()),
+        ),
+        (
+            &activity,
+            turtle::IRI::prefix("prov", "used"),
+            turtle::TripleObject::from(config.clone()),
+        ),
+        (
+            &activity,
+            turtle::IRI::prefix("prov", "wasAssociatedWith"),
+            turtle::TripleObject::from(agent.clone()),
+        ),
+        (
+            &activity,
+            turtle::IRI::prefix("prov", "endedAtTime"),
+            date_time_literal(now),
+        ),
+        (
+            &agent,
+            RDF_TYPE_IRI.clone(),
+            turtle::TripleObject::from(turtle::IRI::prefix("prov", "SoftwareAgent")),
+        ),
+        (
+            &agent,
+            turtle::IRI::prefix("dicom2rdf", "version"),
+            string_literal(converter.version),
+        ),
+        (
+            &config,
+            RDF_TYPE_IRI.clone(),
+            turtle::TripleObject::from(turtle::IRI::prefix("prov", "Entity")),
+        ),
+        (
+            &config,
+            turtle::IRI::prefix("dicom2rdf", "sha256"),
+            string_literal(converter.config_sha256),
+        ),
+    ];
+    if let Some(sha256) = source.sha256 {
+        triples.push((
+            &source_node,
+            turtle::IRI::prefix("dicom2rdf", "sha256"),
+            string_literal(sha256),
+        ));
+    }
+    if let Some(path) = source.path {
+        triples.push((
+            &source_node,
+            turtle::IRI::prefix("dicom2rdf", "source_path"),
+            string_literal(path),
+        ));
+    }
+    if let Some(archive_member) = source.archive_member {
+        triples.push((
+            &source_node,
+            turtle::IRI::prefix("dicom2rdf", "archive_member"),
+            string_literal(archive_member),
+        ));
+    }
+    // The file system modification time is the best guess for when the source was generated.
+    if let Some(modified) = source.modified {
+        triples.push((
+            &source_node,
+            turtle::IRI::prefix("prov", "generatedAtTime"),
+            date_time_literal(DateTime::<Utc>::from(modified)),
+        ));
+    }
+    for (subject, predicate, object) in &triples {
+        writer.write_triple(turtle::triple(subject, predicate, object))?;
+    }
+    Ok(())
+}
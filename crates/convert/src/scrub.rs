@@ -0,0 +1,180 @@
+use config::scrubbing::Scrubbing;
+use dicom::core::VR;
+use dicom::object::InMemDicomObject;
+use regex::{Captures, Regex};
+use std::collections::{BTreeMap, BTreeSet};
+
+const NAME_LABEL: &str = "NAME";
+const MIN_NAME_LENGTH: usize = 2;
+
+pub struct Scrubber<'a> {
+    scrubbing: &'a Scrubbing,
+    names: Option<Regex>,
+}
+
+// Family, given and middle names of every person name in the document, in all component groups.
+fn collect_names(dicom_object: &InMemDicomObject, names: &mut BTreeSet<String>) {
+    for data_element in dicom_object.iter() {
+        match data_element.vr() {
+            VR::PN => {
+                let Ok(values) = data_element.value().strings() else {
+                    continue;
+                };
+                for value in values {
+                    for group in value.split('=') {
+                        names.extend(
+                            group
+                                .split('^')
+                                .take(3)
+                                .map(|component| component.trim())
+                                .filter(|component| component.chars().count() >= MIN_NAME_LENGTH)
+                                .map(str::to_string),
+                        );
+                    }
+                }
+            }
+            VR::SQ => {
+                for item in data_element.items().into_iter().flatten() {
+                    collect_names(item, names);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'a> Scrubber<'a> {
+    pub fn new(scrubbing: &'a Scrubbing, dicom_object: &InMemDicomObject) -> Self {
+        let mut names = BTreeSet::new();
+        collect_names(dicom_object, &mut names);
+        // Longer names first, so that a name is not partially replaced by one of its prefixes.
+        let mut names: Vec<String> = names.into_iter().collect();
+        names.sort_by_key(|name| std::cmp::Reverse(name.chars().count()));
+        let names = (!names.is_empty())
+            .then(|| {
+                let alternatives: Vec<String> = names.iter().map(|n| regex::escape(n)).collect();
+                Regex::new(&format!(r"(?i)\b(?:{})\b", alternatives.join("|"))).ok()
+            })
+            .flatten();
+        Self { scrubbing, names }
+    }
+
+    pub fn scrub(&self, text: &str, counts: &mut BTreeMap<String, usize>) -> String {
+        let mut scrubbed = text.to_string();
+        let rules = self.names.iter().map(|regex| (NAME_LABEL, regex)).chain(
+            self.scrubbing
+                .patterns
+                .iter()
+                .map(|pattern| (pattern.label.as_str(), &pattern.regex)),
+        );
+        for (label, regex) in rules {
+            let mut replaced = 0;
+            let result = regex.replace_all(&scrubbed, |_: &Captures| {
+                replaced += 1;
+                format!("[{}]", label)
+            });
+            if replaced > 0 {
+                scrubbed = result.into_owned();
+                *counts.entry(label.to_string()).or_default() += replaced;
+            }
+        }
+        scrubbed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::scrubbing::ScrubPattern;
+    use dicom::core::{DataElement, PrimitiveValue, Tag};
+    use dicom::object::mem::InMemElement;
+
+    fn person_name(tag: Tag, name: &str) -> InMemElement {
+        DataElement::new(tag, VR::PN, PrimitiveValue::from(name))
+    }
+
+    fn scrubbing(patterns: &[(&str, &str)]) -> Scrubbing {
+        Scrubbing {
+            patterns: patterns
+                .iter()
+                .map(|(label, regex)| ScrubPattern {
+                    label: label.to_string(),
+                    regex: Regex::new(regex).unwrap(),
+                })
+                .collect(),
+        }
+    }
+
+    fn scrub(scrubber: &Scrubber, text: &str) -> (String, BTreeMap<String, usize>) {
+        let mut counts = BTreeMap::new();
+        let scrubbed = scrubber.scrub(text, &mut counts);
+        (scrubbed, counts)
+    }
+
+    #[test]
+    fn names_of_the_document_are_replaced() {
+        let observer = InMemDicomObject::from_element_iter([person_name(
+            Tag(0x0040, 0xA123),
+            "Mustermann^Erika^Maria^Dr.",
+        )]);
+        let dicom_object = InMemDicomObject::from_element_iter([
+            person_name(Tag(0x0010, 0x0010), "Doe^J=Ideographic^Kanji"),
+            DataElement::new(
+                Tag(0x0040, 0xA730),
+                VR::SQ,
+                dicom::core::value::DataSetSequence::from(vec![observer]),
+            ),
+        ]);
+        let scrubbing = scrubbing(&[]);
+        let scrubber = Scrubber::new(&scrubbing, &dicom_object);
+        let (scrubbed, counts) = scrub(
+            &scrubber,
+            "DOE and Dr. Erika Maria MUSTERMANN, J. Doe's kanji; Doerte, Dr.",
+        );
+        assert_eq!(
+            scrubbed,
+            "[NAME] and Dr. [NAME] [NAME] [NAME], J. [NAME]'s [NAME]; Doerte, Dr."
+        );
+        assert_eq!(counts.get(NAME_LABEL), Some(&6));
+    }
+
+    #[test]
+    fn longer_names_are_replaced_first() {
+        let dicom_object =
+            InMemDicomObject::from_element_iter([person_name(Tag(0x0010, 0x0010), "van Dyke^van")]);
+        let scrubbing = scrubbing(&[]);
+        let scrubber = Scrubber::new(&scrubbing, &dicom_object);
+        let (scrubbed, counts) = scrub(&scrubber, "Seen by van Dyke.");
+        assert_eq!(scrubbed, "Seen by [NAME].");
+        assert_eq!(counts.get(NAME_LABEL), Some(&1));
+    }
+
+    #[test]
+    fn patterns_are_replaced_by_their_label() {
+        let scrubbing = scrubbing(&[
+            ("DATE", r"\b\d{1,2}\.\d{1,2}\.\d{2,4}\b"),
+            ("PHONE", r"\+49[\d /-]{6,}\d\b"),
+        ]);
+        let scrubber = Scrubber::new(&scrubbing, &InMemDicomObject::new_empty());
+        let (scrubbed, counts) = scrub(
+            &scrubber,
+            "Seen on 1.2.2024 and 03.04.24, call +49 761 270-0.",
+        );
+        assert_eq!(scrubbed, "Seen on [DATE] and [DATE], call [PHONE].");
+        assert_eq!(counts.get("DATE"), Some(&2));
+        assert_eq!(counts.get("PHONE"), Some(&1));
+        assert_eq!(counts.get(NAME_LABEL), None);
+    }
+
+    #[test]
+    fn names_are_matched_literally() {
+        let dicom_object =
+            InMemDicomObject::from_element_iter([person_name(Tag(0x0010, 0x0010), "O.Ne+il^Jo")]);
+        let scrubbing = scrubbing(&[]);
+        let scrubber = Scrubber::new(&scrubbing, &dicom_object);
+        assert_eq!(
+            scrub(&scrubber, "O.Ne+il vs OxNeeil").0,
+            "[NAME] vs OxNeeil"
+        );
+    }
+}
@@ -0,0 +1,1194 @@
+use crate::io::{raw_namespace, raw_prefixes};
+use crate::turtle::{self, TripleSink};
+use config::Config;
+use config::semantic::{CONTEXTS, MODIFIERS, RELATIONSHIPS, TEMPLATES};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::sync::LazyLock;
+
+static DECIMAL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[+-]?([0-9]+(\.[0-9]*)?|\.[0-9]+)$").unwrap());
+
+static XSD_DATE_TIME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^-?[0-9]{4,}-[0-9]{2}-[0-9]{2}T[0-9]{2}:[0-9]{2}:[0-9]{2}(\.[0-9]+)?(Z|[+-][0-9]{2}:[0-9]{2})?$",
+    )
+    .unwrap()
+});
+
+// Prefixes of the semantic predicates and classes, besides the raw ones.
+const SEMANTIC_PREFIXES: [&str; 6] = ["dcm", "ln", "qudt", "rad", "schema", "sct"];
+
+const SIMPLE: [(&str, &str); 9] = [
+    ("dcm:121022", "dicom2rdf:00080050"),
+    ("dcm:121194", "dicom2rdf:00080070"),
+    ("dcm:121195", "dicom2rdf:00081090"),
+    ("dcm:121139", "dicom2rdf:00080060"),
+    ("dicom2rdf:00181030", "dicom2rdf:00181030"),
+    ("dcm:121095", "dicom2rdf:00080090"),
+    ("dicom2rdf:0008103E", "dicom2rdf:0008103E"),
+    ("dcm:110119", "dicom2rdf:00080055"),
+    ("dicom2rdf:00081030", "dicom2rdf:00081030"),
+];
+
+const DATE_TIMES: [(&str, &str, &str); 3] = [
+    ("rad:cdt", "dicom2rdf:00080023", "dicom2rdf:00080033"),
+    ("rad:seriesDt", "dicom2rdf:00080021", "dicom2rdf:00080031"),
+    ("rad:studyDt", "dicom2rdf:00080020", "dicom2rdf:00080030"),
+];
+
+const UIDS: [(&str, &str); 4] = [
+    ("dcm:112002", "dicom2rdf:0020000E"),
+    ("dcm:110181", "dicom2rdf:00080016"),
+    ("dcm:110180", "dicom2rdf:0020000D"),
+    ("rad:siuid", "dicom2rdf:00080018"),
+];
+
+const LABELS: [(&str, &str); 32] = [
+    ("rad:acquisitionContext", "acquisition context"),
+    (
+        "rad:BasicDiagnosticImagingReport",
+        "Basic Diagnostic Imaging Report",
+    ),
+    ("rad:cdt", "Content Date Time"),
+    ("rad:CTRadiationDoseReport", "CT Radiation Dose"),
+    ("rad:denominator", "Rational Denominator Value"),
+    ("rad:derivation", "Derivation"),
+    ("rad:frame", "Referenced Frame Number"),
+    ("rad:frameOfReference", "Frame of Reference"),
+    ("rad:hasConceptModifier", "has concept modifier"),
+    ("rad:hasProperties", "has properties"),
+    ("rad:image", "Image"),
+    ("rad:inferredFrom", "inferred from"),
+    ("rad:mappingResource", "Mapping Resource"),
+    ("rad:MeasurementReport", "Measurement Report"),
+    ("rad:method", "Measurement Method"),
+    ("rad:numerator", "Rational Numerator Value"),
+    ("rad:observationContext", "observation context"),
+    ("rad:patient", "patient"),
+    ("rad:PatientRadiationDoseReport", "Patient Radiation Dose"),
+    (
+        "rad:ProjectionXRayRadiationDoseReport",
+        "Projection X-Ray Radiation Dose",
+    ),
+    ("rad:qualifier", "Numeric Value Qualifier"),
+    ("rad:RadiationDoseReport", "Radiation Dose Report"),
+    (
+        "rad:RadiopharmaceuticalRadiationDoseReport",
+        "Radiopharmaceutical Radiation Dose",
+    ),
+    ("rad:segment", "Referenced Segment Number"),
+    ("rad:selectedFrom", "selected from"),
+    ("rad:seriesDt", "Series Date Time"),
+    ("rad:siuid", "SOP Instance UID"),
+    ("rad:studyDt", "Study Date Time"),
+    ("rad:template", "template"),
+    ("rad:templateIdentifier", "Template Identifier"),
+    ("rad:value", "value"),
+    ("sct:71388002", "Procedure"),
+];
+
+const PERSON_NAME: [(&str, &str); 5] = [
+    ("schema:familyName", "dicom2rdf:pn_family"),
+    ("schema:additionalName", "dicom2rdf:pn_middle"),
+    ("schema:givenName", "dicom2rdf:pn_given"),
+    ("schema:honorificPrefix", "dicom2rdf:pn_prefix"),
+    ("schema:honorificSuffix", "dicom2rdf:pn_suffix"),
+];
+
+// Raw DATE, TIME and DATETIME values are typed already, values replaced by de-identification are
+// plain strings and left out.
+const TEMPORAL_VALUES: [(&str, &str, &[&str]); 3] = [
+    (
+        "DATE",
+        "dicom2rdf:0040A121",
+        &["xsd:date", "xsd:gYearMonth", "xsd:gYear"],
+    ),
+    ("TIME", "dicom2rdf:0040A122", &["xsd:time"]),
+    (
+        "DATETIME",
+        "dicom2rdf:0040A120",
+        &["xsd:dateTime", "xsd:date", "xsd:gYearMonth", "xsd:gYear"],
+    ),
+];
+
+// Attributes of the Referenced SOP Sequence items of IMAGE, COMPOSITE and WAVEFORM content items.
+const REFERENCED_SOP: [(&str, &str); 3] = [
+    ("dcm:110181", "dicom2rdf:00081150"),
+    ("rad:frame", "dicom2rdf:00081160"),
+    ("rad:segment", "dicom2rdf:0062000B"),
+];
+
+const DOSE_REPORTS: [&str; 4] = [
+    "rad:CTRadiationDoseReport",
+    "rad:PatientRadiationDoseReport",
+    "rad:ProjectionXRayRadiationDoseReport",
+    "rad:RadiopharmaceuticalRadiationDoseReport",
+];
+
+const PATIENT: [(&str, &str); 7] = [
+    ("schema:identifier", "dicom2rdf:00100020"),
+    ("dcm:110190", "dicom2rdf:00100021"),
+    ("schema:birthDate", "dicom2rdf:00100030"),
+    ("ln:LP97565-3", "dicom2rdf:00100032"),
+    ("rad:age", "dicom2rdf:00101010"),
+    ("schema:address", "dicom2rdf:00101040"),
+    ("rdfs:comment", "dicom2rdf:00104000"),
+];
+
+const GENDERS: [(&str, &str); 4] = [
+    ("M", "male"),
+    ("F", "female"),
+    ("W", "female"),
+    ("O", "other"),
+];
+
+// The triples of a document, serialized for the raw output and kept for the semantic mapping.
+pub struct DocumentTriples {
+    pub turtle: Vec<u8>,
+    triples: Option<Vec<(turtle::IRI, turtle::IRI, turtle::TripleObject)>>,
+}
+
+impl DocumentTriples {
+    pub fn new(keep: bool) -> Self {
+        Self {
+            turtle: Vec::new(),
+            triples: keep.then(Vec::new),
+        }
+    }
+}
+
+impl TripleSink for DocumentTriples {
+    fn write_triple(&mut self, triple: turtle::Triple) -> std::io::Result<()> {
+        self.turtle.write_triple(turtle::triple(
+            triple.subject,
+            triple.predicate,
+            triple.object,
+        ))?;
+        if let Some(triples) = &mut self.triples {
+            triples.push((
+                triple.subject.clone(),
+                triple.predicate.clone(),
+                triple.object.clone(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+// A term of the raw triples with its untruncated Turtle serialization, which semantic triples copy.
+#[derive(PartialEq, Eq)]
+struct TermData {
+    token: String,
+    str: Option<String>,
+    datatype: Option<String>,
+    is_iri: bool,
+    is_simple: bool,
+}
+
+impl TermData {
+    fn new(object: &turtle::TripleObject, prefixes: &[(String, String)]) -> Self {
+        let (str, datatype, is_simple) = match object {
+            turtle::TripleObject::IRI(iri) => (iri_string(iri, prefixes), None, false),
+            turtle::TripleObject::PlainLiteral(turtle::PlainLiteral::String(s)) => {
+                (Some(s.clone()), None, true)
+            }
+            turtle::TripleObject::PlainLiteral(turtle::PlainLiteral::LangString(s, _)) => {
+                (Some(s.clone()), None, false)
+            }
+            turtle::TripleObject::PlainLiteral(turtle::PlainLiteral::Integer(n)) => {
+                (Some(n.to_string()), Some("xsd:integer".to_string()), false)
+            }
+            turtle::TripleObject::TypedLiteral(literal) => (
+                Some(literal.lexical().to_string()),
+                Some(literal.datatype().to_string()),
+                false,
+            ),
+        };
+        Self {
+            token: object.to_string(),
+            str,
+            datatype,
+            is_iri: matches!(object, turtle::TripleObject::IRI(_)),
+            is_simple,
+        }
+    }
+}
+
+// The IRI string of an IRI, with its prefix expanded.
+fn iri_string(iri: &turtle::IRI, prefixes: &[(String, String)]) -> Option<String> {
+    let namespace = |prefix: &str| {
+        prefixes
+            .iter()
+            .find(|(p, _)| p == prefix)
+            .map(|(_, iri)| iri)
+    };
+    match iri {
+        turtle::IRI::Full(iri) => Some(iri.clone()),
+        turtle::IRI::Prefixed { prefix, local } => Some(format!(
+            "{}{}",
+            namespace(prefix)?,
+            urlencoding::encode(local)
+        )),
+        turtle::IRI::PrefixedPath { prefix, segments } => Some(format!(
+            "{}{}",
+            namespace(prefix)?,
+            segments
+                .iter()
+                .map(|segment| urlencoding::encode(segment))
+                .collect::<Vec<_>>()
+                .join("/")
+        )),
+    }
+}
+
+// The raw triples of a single document, by subject.
+struct Graph {
+    nodes: HashMap<String, (TermData, Vec<(String, TermData)>)>,
+}
+
+impl Graph {
+    fn new(triples: &DocumentTriples, prefixes: &[(String, String)]) -> Self {
+        let mut nodes: HashMap<String, (TermData, Vec<(String, TermData)>)> = HashMap::new();
+        for (subject, predicate, object) in triples.triples.iter().flatten() {
+            let subject = TermData::new(&turtle::TripleObject::from(subject.clone()), prefixes);
+            nodes
+                .entry(subject.token.clone())
+                .or_insert_with(|| (subject, Vec::new()))
+                .1
+                .push((predicate.to_string(), TermData::new(object, prefixes)));
+        }
+        Self { nodes }
+    }
+
+    fn subjects(&self, predicate: &str, object: &str) -> Vec<Term<'_>> {
+        let mut subjects = self
+            .nodes
+            .values()
+            .filter(|(_, edges)| {
+                edges
+                    .iter()
+                    .any(|(p, o)| p == predicate && o.token == object)
+            })
+            .map(|(subject, _)| Term(subject))
+            .collect::<Vec<_>>();
+        subjects.sort_by_key(|subject| subject.token());
+        subjects
+    }
+
+    fn objects<'a>(&'a self, subject: Term, predicate: &str) -> Vec<Term<'a>> {
+        self.nodes
+            .get(subject.token())
+            .into_iter()
+            .flat_map(|(_, edges)| edges)
+            .filter(|(p, _)| p == predicate)
+            .map(|(_, o)| Term(o))
+            .collect()
+    }
+
+    // Objects reached by a SPARQL sequence path.
+    fn path_objects<'a>(&'a self, subject: Term<'a>, path: &[&str]) -> Vec<Term<'a>> {
+        path.iter().fold(vec![subject], |terms, predicate| {
+            terms
+                .into_iter()
+                .flat_map(|term| self.objects(term, predicate))
+                .collect()
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Term<'a>(&'a TermData);
+
+impl<'a> Term<'a> {
+    fn token(&self) -> &'a str {
+        &self.0.token
+    }
+
+    // The lexical form of a literal or the IRI string, like STR() in SPARQL.
+    fn str(&self) -> Option<String> {
+        self.0.str.clone()
+    }
+
+    fn datatype(&self) -> Option<&'a str> {
+        self.0.datatype.as_deref()
+    }
+
+    fn is_literal(&self) -> bool {
+        !self.0.is_iri
+    }
+
+    fn is_simple_literal(&self, s: &str) -> bool {
+        self.0.is_simple && self.0.str.as_deref() == Some(s)
+    }
+
+    fn numeric_value(&self) -> Option<f64> {
+        match self.datatype()? {
+            "xsd:integer" | "xsd:decimal" | "xsd:double" | "xsd:float" => self.str()?.parse().ok(),
+            _ => None,
+        }
+    }
+
+    // The lexical form of xsd:decimal(term), unbound if the cast fails.
+    fn decimal(&self) -> Option<String> {
+        let lexical = self.str()?;
+        if DECIMAL.is_match(&lexical) {
+            return Some(lexical);
+        }
+        match self.datatype()? {
+            "xsd:double" | "xsd:float" => lexical
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .map(|value| value.to_string()),
+            _ => None,
+        }
+    }
+}
+
+// A triple of the CONSTRUCT templates, it is left out if one of its terms is unbound.
+type Template = [Option<String>; 3];
+
+#[derive(Default)]
+struct Triples {
+    seen: HashSet<String>,
+    lines: Vec<String>,
+}
+
+impl Triples {
+    fn add(&mut self, subject: &str, predicate: &str, object: &str) {
+        let line = format!("{} {} {} .", subject, predicate, object);
+        if self.seen.insert(line.clone()) {
+            self.lines.push(line);
+        }
+    }
+
+    fn add_bound(&mut self, [subject, predicate, object]: &Template) {
+        if let (Some(subject), Some(predicate), Some(object)) = (subject, predicate, object) {
+            self.add(subject, predicate, object);
+        }
+    }
+}
+
+fn bound(subject: &Option<String>, predicate: &str, object: &Option<String>) -> Template {
+    [subject.clone(), Some(predicate.to_string()), object.clone()]
+}
+
+// IRI() in SPARQL, unbound for strings that are not absolute IRIs.
+fn absolute_iri(s: String) -> Option<String> {
+    turtle::is_absolute_iri(&s).then_some(s)
+}
+
+fn uid_iri(uid: Term) -> Option<String> {
+    uid.str()
+        .and_then(|uid| absolute_iri(format!("urn:oid:{}", uid)))
+        .map(|iri| full(&iri))
+}
+
+fn full(iri: &str) -> String {
+    turtle::IRI::full(iri).to_string()
+}
+
+fn string_literal(s: &str) -> String {
+    turtle::TripleObject::from(turtle::PlainLiteral::String(s.to_string())).to_string()
+}
+
+fn decimal_literal(lexical: String) -> String {
+    turtle::TypedLiteral::new(lexical, turtle::IRI::prefix("xsd", "decimal")).to_string()
+}
+
+// Multiplies a decimal by 100 on its lexical form, which keeps it exact.
+fn centuple(decimal: &str) -> String {
+    let (sign, digits) = match decimal.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", decimal.strip_prefix('+').unwrap_or(decimal)),
+    };
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let fraction = format!("{:0<2}", fraction);
+    let (shifted, fraction) = fraction.split_at(2);
+    let integer = format!("{}{}", integer, shifted);
+    let integer = match integer.trim_start_matches('0') {
+        "" => "0",
+        integer => integer,
+    };
+    if fraction.is_empty() {
+        format!("{}{}", sign, integer)
+    } else {
+        format!("{}{}.{}", sign, integer, fraction)
+    }
+}
+
+// Solutions of an OPTIONAL pattern, a single unbound one if it does not match.
+fn optional<T>(terms: Vec<T>) -> Vec<Option<T>> {
+    if terms.is_empty() {
+        vec![None]
+    } else {
+        terms.into_iter().map(Some).collect()
+    }
+}
+
+// The IRI a string literal is mapped to, if any.
+fn mapping(term: Option<Term>, cases: &[(&str, &'static str)]) -> Option<&'static str> {
+    let term = term?;
+    cases
+        .iter()
+        .find(|(value, _)| term.is_simple_literal(value))
+        .map(|(_, iri)| *iri)
+}
+
+struct Code<'a> {
+    code: Term<'a>,
+    scheme: Term<'a>,
+    meaning: Term<'a>,
+}
+
+impl Code<'_> {
+    fn iri(&self) -> Option<String> {
+        absolute_iri(format!(
+            "{}{}",
+            self.scheme.str()?,
+            urlencoding::encode(&self.code.str()?)
+        ))
+    }
+}
+
+fn cross_product<'a>(
+    codes: Vec<Term<'a>>,
+    schemes: Vec<Term<'a>>,
+    meanings: Vec<Term<'a>>,
+) -> Vec<Code<'a>> {
+    let mut product = Vec::new();
+    for code in &codes {
+        for scheme in &schemes {
+            for meaning in &meanings {
+                product.push(Code {
+                    code: *code,
+                    scheme: *scheme,
+                    meaning: *meaning,
+                });
+            }
+        }
+    }
+    product
+}
+
+// Codes of the items of a code sequence, like (0040,A043) Concept Name Code Sequence.
+fn codes<'a>(graph: &'a Graph, subject: Term<'a>, predicate: &str) -> Vec<Code<'a>> {
+    graph
+        .objects(subject, predicate)
+        .into_iter()
+        .flat_map(|item| {
+            cross_product(
+                graph.objects(item, "dicom2rdf:00080100"),
+                graph.objects(item, "dicom2rdf:00080102"),
+                graph.objects(item, "dicom2rdf:00080104"),
+            )
+        })
+        .collect()
+}
+
+// Writes the semantic triples of each document, the constant labels once per file.
+pub struct SemanticWriter<W: Write> {
+    writer: W,
+    labels_written: bool,
+}
+
+impl<W: Write> SemanticWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            labels_written: false,
+        }
+    }
+}
+
+// The semantic mapping of the construct crate's CONSTRUCT queries, applied to the raw triples of a
+// single document. Raw terms are copied as they are, so raw prefixes missing from the config are
+// declared as well.
+pub struct SemanticMapping {
+    rad_iri: String,
+    modifiers: Vec<(String, &'static str)>,
+    prefixes: Vec<(String, String)>,
+}
+
+impl SemanticMapping {
+    pub fn new(config: &Config) -> Result<Self, String> {
+        let mut prefixes = config
+            .to_prefix_iri_pairs()
+            .map(|(prefix, iri)| (prefix.to_string(), iri.to_string()))
+            .collect::<Vec<_>>();
+        for (prefix, iri) in raw_prefixes(raw_namespace(config)?) {
+            match prefixes.iter().find(|(p, _)| p == prefix) {
+                Some((_, declared)) if declared != iri => {
+                    return Err(format!(
+                        "Prefix '{}' is declared as <{}>, but raw triples use <{}>",
+                        prefix, declared, iri
+                    ));
+                }
+                Some(_) => {}
+                None => prefixes.push((prefix.to_string(), iri.to_string())),
+            }
+        }
+        if let Some(prefix) = SEMANTIC_PREFIXES
+            .iter()
+            .find(|prefix| !prefixes.iter().any(|(p, _)| p == *prefix))
+        {
+            return Err(format!("Semantic mapping needs prefix '{}'", prefix));
+        }
+        let rad_iri = prefixes
+            .iter()
+            .find(|(p, _)| p == "rad")
+            .map(|(_, iri)| iri.clone())
+            .unwrap_or_default();
+        let modifiers = MODIFIERS
+            .iter()
+            .filter_map(|(term, modifier)| {
+                let (prefix, code) = term.split_once(':')?;
+                let (_, iri) = prefixes.iter().find(|(p, _)| p == prefix)?;
+                Some((full(&format!("{}{}", iri, code)), *modifier))
+            })
+            .collect();
+        Ok(Self {
+            rad_iri,
+            modifiers,
+            prefixes,
+        })
+    }
+
+    pub fn prefixes(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.prefixes
+            .iter()
+            .map(|(prefix, iri)| (prefix.as_str(), iri.as_str()))
+    }
+
+    pub fn write_document(
+        &self,
+        writer: &mut SemanticWriter<impl Write>,
+        document: &DocumentTriples,
+    ) -> std::io::Result<()> {
+        let graph = &Graph::new(document, &self.prefixes);
+        let mut triples = Triples::default();
+        for root in graph.subjects("rdf:type", "dicom2rdf:DocumentRoot") {
+            for sop_instance_uid in graph.objects(root, "dicom2rdf:00080018") {
+                let Some(level0) = sop_instance_uid
+                    .str()
+                    .and_then(|uid| absolute_iri(format!("{}sopInstance/{}", self.rad_iri, uid)))
+                else {
+                    continue;
+                };
+                if !writer.labels_written {
+                    for (subject, label) in LABELS {
+                        let label = turtle::TripleObject::from(turtle::PlainLiteral::LangString(
+                            label.to_string(),
+                            "en".to_string(),
+                        ));
+                        triples.add(subject, "rdfs:label", &label.to_string());
+                    }
+                    for class in DOSE_REPORTS {
+                        triples.add(class, "rdfs:subClassOf", "rad:RadiationDoseReport");
+                    }
+                    writer.labels_written = true;
+                }
+                write_top_level(&mut triples, graph, root, &level0);
+                for template in templates(graph, root, &self.rad_iri, &Some(full(&level0))) {
+                    triples.add_bound(&template);
+                }
+                let mut nodes = HashMap::new();
+                content_nodes(graph, root, &level0, &mut nodes);
+                write_content(
+                    &mut triples,
+                    graph,
+                    self,
+                    &nodes,
+                    root,
+                    Some(&level0),
+                    &mut Vec::new(),
+                );
+            }
+        }
+        for line in triples.lines {
+            writeln!(writer.writer, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_top_level(triples: &mut Triples, graph: &Graph, root: Term, level0: &str) {
+    let level0_iri = full(level0);
+    for (predicate, tag) in SIMPLE {
+        for object in graph.objects(root, tag) {
+            if !object.is_literal() {
+                continue;
+            }
+            triples.add(&level0_iri, predicate, object.token());
+        }
+    }
+    for (predicate, date_tag, time_tag) in DATE_TIMES {
+        for date in graph.objects(root, date_tag) {
+            for time in optional(graph.objects(root, time_tag)) {
+                let Some(time) = time else {
+                    triples.add(&level0_iri, predicate, date.token());
+                    continue;
+                };
+                if let (Some(date), Some(time)) = (date.str(), time.str()) {
+                    let date_time = format!("{}T{}", date, time);
+                    if XSD_DATE_TIME.is_match(&date_time) {
+                        let object = turtle::TypedLiteral::new(
+                            date_time,
+                            turtle::IRI::prefix("xsd", "dateTime"),
+                        );
+                        triples.add(&level0_iri, predicate, &object.to_string());
+                    }
+                }
+            }
+        }
+    }
+    for (predicate, tag) in UIDS {
+        for uid in graph.objects(root, tag) {
+            if let Some(uid) = uid_iri(uid) {
+                triples.add(&level0_iri, predicate, &uid);
+            }
+        }
+    }
+    write_patient(triples, graph, root, level0);
+    let procedures = cross_product(
+        graph.path_objects(root, &["dicom2rdf:00081032", "dicom2rdf:00080100"]),
+        graph.path_objects(root, &["dicom2rdf:00081032", "dicom2rdf:00080102"]),
+        graph.path_objects(root, &["dicom2rdf:00081032", "dicom2rdf:00080104"]),
+    );
+    for procedure in procedures {
+        if let Some(iri) = procedure.iri() {
+            triples.add(&level0_iri, "sct:71388002", &full(&iri));
+            triples.add(&full(&iri), "rdfs:label", procedure.meaning.token());
+        }
+    }
+    for document_type in codes(graph, root, "dicom2rdf:0040A043") {
+        if let Some(iri) = document_type.iri() {
+            triples.add(&level0_iri, "rdf:type", &full(&iri));
+            triples.add(&full(&iri), "rdfs:label", document_type.meaning.token());
+        }
+    }
+}
+
+fn write_patient(triples: &mut Triples, graph: &Graph, root: Term, level0: &str) {
+    let names = graph.path_objects(root, &["dicom2rdf:00100010", "dicom2rdf:pn_alphabetic"]);
+    if names.is_empty() {
+        return;
+    }
+    let patient = full(&format!("{}_patient", level0));
+    triples.add(&full(level0), "rad:patient", &patient);
+    for name in names {
+        for (predicate, component) in PERSON_NAME {
+            for object in graph.objects(name, component) {
+                triples.add(&patient, predicate, object.token());
+            }
+        }
+    }
+    for (predicate, tag) in PATIENT {
+        for object in graph.objects(root, tag) {
+            triples.add(&patient, predicate, object.token());
+        }
+    }
+    for sex in graph.objects(root, "dicom2rdf:00100040") {
+        let gender = GENDERS
+            .iter()
+            .find(|(code, _)| sex.is_simple_literal(code))
+            .map(|(_, gender)| string_literal(gender));
+        triples.add(
+            &patient,
+            "schema:gender",
+            gender.as_deref().unwrap_or(sex.token()),
+        );
+    }
+    // Sizes of up to 3 are taken to be in metres and converted to centimetres.
+    for size in graph.objects(root, "dicom2rdf:00101020") {
+        if !size.numeric_value().is_some_and(|size| size > 0.0) {
+            continue;
+        }
+        if let Some(size) = size.decimal() {
+            let height = if size.parse::<f64>().is_ok_and(|size| size <= 3.0) {
+                centuple(&size)
+            } else {
+                size
+            };
+            triples.add(&patient, "schema:height", &decimal_literal(height));
+        }
+    }
+    for weight in graph.objects(root, "dicom2rdf:00101030") {
+        if weight.numeric_value().is_some_and(|weight| weight > 0.0) {
+            triples.add(&patient, "schema:weight", weight.token());
+        }
+    }
+}
+
+// The template declared in the Content Template Sequence of `node`.
+fn templates(graph: &Graph, node: Term, rad_iri: &str, node_iri: &Option<String>) -> Vec<Template> {
+    let mut templates = Vec::new();
+    for template in graph.objects(node, "dicom2rdf:0040A504") {
+        for mapping_resource in graph.objects(template, "dicom2rdf:00080105") {
+            for identifier in graph.objects(template, "dicom2rdf:0040DB00") {
+                let (Some(resource), Some(id)) = (mapping_resource.str(), identifier.str()) else {
+                    continue;
+                };
+                let template_iri = absolute_iri(format!(
+                    "{}template/{}/{}",
+                    rad_iri,
+                    urlencoding::encode(&resource),
+                    urlencoding::encode(&id)
+                ))
+                .map(|iri| full(&iri));
+                let key = format!("{}/{}", resource, id);
+                let class = TEMPLATES
+                    .iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, class)| class.to_string());
+                templates.extend([
+                    bound(node_iri, "rad:template", &template_iri),
+                    bound(
+                        &template_iri,
+                        "rad:mappingResource",
+                        &Some(mapping_resource.token().to_string()),
+                    ),
+                    bound(
+                        &template_iri,
+                        "rad:templateIdentifier",
+                        &Some(identifier.token().to_string()),
+                    ),
+                    bound(node_iri, "rdf:type", &class),
+                ]);
+            }
+        }
+    }
+    templates
+}
+
+// The node of a content item below the node `iri`. Containers are named after their concept name,
+// other items after their index.
+fn level_iri(
+    iri: Option<&str>,
+    index: Term,
+    is_container: bool,
+    concept_name: &Code,
+) -> Option<String> {
+    if !is_container {
+        return iri
+            .zip(index.str())
+            .and_then(|(iri, index)| absolute_iri(format!("{}_{}", iri, index)));
+    }
+    iri.zip(index.str())
+        .zip(concept_name.meaning.str())
+        .and_then(|((iri, index), meaning)| {
+            absolute_iri(format!(
+                "{}_{}_{}",
+                iri,
+                index,
+                urlencoding::encode(&meaning)
+            ))
+        })
+}
+
+// The nodes of `node` and the content items below it by their raw term, the targets of
+// by-reference relationships.
+fn content_nodes<'a>(
+    graph: &'a Graph,
+    node: Term<'a>,
+    iri: &str,
+    nodes: &mut HashMap<&'a str, Vec<String>>,
+) {
+    nodes.entry(node.token()).or_default().push(iri.to_string());
+    for content_item in graph.objects(node, "dicom2rdf:0040A730") {
+        for index in graph.objects(content_item, "dicom2rdf:index") {
+            for item in graph.objects(content_item, "dicom2rdf:item") {
+                let value_types = graph.objects(item, "dicom2rdf:0040A040");
+                if value_types.is_empty() {
+                    continue;
+                }
+                let is_container = value_types.iter().any(|t| t.is_simple_literal("CONTAINER"));
+                for concept_name in codes(graph, item, "dicom2rdf:0040A043") {
+                    if let Some(level) = level_iri(Some(iri), index, is_container, &concept_name) {
+                        content_nodes(graph, item, &level, nodes);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Content items of CODE, NUM, TEXT, UIDREF, DATE, TIME, DATETIME, PNAME, SCOORD, SCOORD3D,
+// TCOORD, IMAGE, COMPOSITE and WAVEFORM value type, below any number of CONTAINER items with a
+// concept name. The triples linking the containers are only written for containers that have such
+// a content item below them.
+fn write_content<'a>(
+    triples: &mut Triples,
+    graph: &'a Graph,
+    semantic: &SemanticMapping,
+    nodes: &HashMap<&str, Vec<String>>,
+    node: Term<'a>,
+    iri: Option<&str>,
+    chain: &mut Vec<Template>,
+) {
+    let parent = iri.map(full);
+    for content_item in graph.objects(node, "dicom2rdf:0040A730") {
+        for index in graph.objects(content_item, "dicom2rdf:index") {
+            for item in graph.objects(content_item, "dicom2rdf:item") {
+                let value_types = graph.objects(item, "dicom2rdf:0040A040");
+                let is_container = value_types.iter().any(|t| t.is_simple_literal("CONTAINER"));
+                let item_iri = iri
+                    .zip(index.str())
+                    .and_then(|(iri, index)| absolute_iri(format!("{}_{}", iri, index)));
+                let item_node = item_iri.as_deref().map(full);
+                for target in graph.objects(item, "dicom2rdf:referenced_content_item") {
+                    for target_iri in nodes.get(target.token()).into_iter().flatten() {
+                        for relationship in optional(graph.objects(item, "dicom2rdf:0040A010")) {
+                            let edge = [
+                                parent.clone(),
+                                mapping(relationship, &RELATIONSHIPS).map(String::from),
+                                Some(full(target_iri)),
+                            ];
+                            for template in chain.iter().chain([&edge]) {
+                                triples.add_bound(template);
+                            }
+                        }
+                    }
+                }
+                for concept_name in codes(graph, item, "dicom2rdf:0040A043") {
+                    let predicate = concept_name.iri().map(|iri| full(&iri));
+                    let label = [
+                        predicate.clone(),
+                        Some("rdfs:label".to_string()),
+                        Some(concept_name.meaning.token().to_string()),
+                    ];
+                    for relationship in optional(graph.objects(item, "dicom2rdf:0040A010")) {
+                        let relationship_predicate = mapping(relationship, &RELATIONSHIPS);
+                        let context_predicate = mapping(relationship, &CONTEXTS);
+                        let subject = match context_predicate {
+                            Some(context) => iri
+                                .and_then(|iri| {
+                                    absolute_iri(format!(
+                                        "{}_{}",
+                                        iri,
+                                        context.trim_start_matches("rad:")
+                                    ))
+                                })
+                                .map(|iri| full(&iri)),
+                            None => parent.clone(),
+                        };
+                        let links = [
+                            [
+                                parent.clone(),
+                                context_predicate.map(String::from),
+                                subject.clone(),
+                            ],
+                            [
+                                parent.clone(),
+                                relationship_predicate.map(String::from),
+                                item_node.clone(),
+                            ],
+                        ];
+                        let mut solutions = item_solutions(
+                            graph,
+                            item,
+                            &value_types,
+                            &subject,
+                            &predicate,
+                            &label,
+                            &item_node,
+                        );
+                        let modifier = semantic
+                            .modifiers
+                            .iter()
+                            .find(|(iri, _)| predicate.as_ref() == Some(iri))
+                            .map(|(_, modifier)| modifier.to_string());
+                        if relationship.is_some_and(|r| r.is_simple_literal("HAS CONCEPT MOD"))
+                            && value_types.iter().any(|t| t.is_simple_literal("CODE"))
+                        {
+                            for concept in codes(graph, item, "dicom2rdf:0040A168") {
+                                let value = concept.iri().map(|iri| full(&iri));
+                                solutions.push(vec![[subject.clone(), modifier.clone(), value]]);
+                            }
+                        }
+                        for solution in solutions {
+                            for template in chain.iter().chain(&links).chain(&solution) {
+                                triples.add_bound(template);
+                            }
+                        }
+                        if value_types.is_empty() {
+                            continue;
+                        }
+                        let level = level_iri(iri, index, is_container, &concept_name);
+                        let level_node = level.as_deref().map(full);
+                        let chain_length = chain.len();
+                        if is_container {
+                            chain.push([parent.clone(), predicate.clone(), level_node.clone()]);
+                            chain.push(label.clone());
+                        }
+                        chain.push([
+                            parent.clone(),
+                            relationship_predicate.map(String::from),
+                            level_node.clone(),
+                        ]);
+                        chain.extend(templates(graph, item, &semantic.rad_iri, &level_node));
+                        write_content(
+                            triples,
+                            graph,
+                            semantic,
+                            nodes,
+                            item,
+                            level.as_deref(),
+                            chain,
+                        );
+                        chain.truncate(chain_length);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// The value of a content item in the shapes of the value type queries, attached to `subject`.
+// NUM, PNAME, coordinate and reference values are the `node` of the item, other values are linked
+// from it.
+fn item_solutions(
+    graph: &Graph,
+    item: Term,
+    value_types: &[Term],
+    subject: &Option<String>,
+    predicate: &Option<String>,
+    label: &Template,
+    node: &Option<String>,
+) -> Vec<Vec<Template>> {
+    let has_value_type =
+        |value_type: &str| value_types.iter().any(|t| t.is_simple_literal(value_type));
+    let mut solutions = Vec::new();
+    if has_value_type("CODE") {
+        for concept in codes(graph, item, "dicom2rdf:0040A168") {
+            let value = concept.iri().map(|iri| full(&iri));
+            solutions.push(vec![
+                [subject.clone(), predicate.clone(), value.clone()],
+                bound(node, "rad:value", &value),
+                label.clone(),
+                bound(&value, "rdfs:label", &Some(concept.meaning.token().into())),
+            ]);
+        }
+    }
+    if has_value_type("TEXT") {
+        for value in graph.objects(item, "dicom2rdf:0040A160") {
+            let value = Some(value.token().to_string());
+            solutions.push(vec![
+                [subject.clone(), predicate.clone(), value.clone()],
+                bound(node, "rad:value", &value),
+                label.clone(),
+            ]);
+        }
+    }
+    if has_value_type("NUM") {
+        let qualifiers = codes(graph, item, "dicom2rdf:0040A301")
+            .into_iter()
+            .filter_map(|qualifier| {
+                let iri = full(&qualifier.iri()?);
+                Some((iri, qualifier.meaning.token().to_string()))
+            })
+            .collect::<Vec<_>>();
+        for measured_value in optional(graph.objects(item, "dicom2rdf:0040A300")) {
+            let objects = |predicate| {
+                measured_value
+                    .map(|measured_value| graph.objects(measured_value, predicate))
+                    .unwrap_or_default()
+            };
+            let units = measured_value
+                .map(|measured_value| codes(graph, measured_value, "dicom2rdf:004008EA"))
+                .unwrap_or_default();
+            let mut values = Vec::new();
+            for fp_value in optional(objects("dicom2rdf:0040A161")) {
+                for num_value in optional(objects("dicom2rdf:0040A30A")) {
+                    values.extend(
+                        fp_value
+                            .and_then(|v| v.decimal())
+                            .or_else(|| num_value.and_then(|v| v.decimal())),
+                    );
+                }
+            }
+            if values.is_empty() && qualifiers.is_empty() {
+                continue;
+            }
+            for unit in optional(units) {
+                let unit_iri = unit
+                    .as_ref()
+                    .and_then(|unit| unit.iri())
+                    .map(|iri| full(&iri));
+                let unit_label = unit.as_ref().map(|unit| unit.meaning.token().to_string());
+                let mut solution = vec![
+                    [subject.clone(), predicate.clone(), node.clone()],
+                    bound(node, "rdf:type", &Some("qudt:QuantityValue".to_string())),
+                    bound(node, "qudt:unit", &unit_iri),
+                    label.clone(),
+                    bound(&unit_iri, "rdfs:label", &unit_label),
+                ];
+                for value in &values {
+                    solution.push(bound(
+                        node,
+                        "qudt:numericValue",
+                        &Some(decimal_literal(value.clone())),
+                    ));
+                }
+                for (component_predicate, component) in [
+                    ("rad:numerator", "dicom2rdf:0040A162"),
+                    ("rad:denominator", "dicom2rdf:0040A163"),
+                ] {
+                    for object in objects(component) {
+                        solution.push(bound(
+                            node,
+                            component_predicate,
+                            &Some(object.token().to_string()),
+                        ));
+                    }
+                }
+                for (qualifier, meaning) in &qualifiers {
+                    let qualifier = Some(qualifier.clone());
+                    solution.push(bound(node, "rad:qualifier", &qualifier));
+                    solution.push(bound(&qualifier, "rdfs:label", &Some(meaning.clone())));
+                }
+                solutions.push(solution);
+            }
+        }
+    }
+    if has_value_type("UIDREF") {
+        for value in graph.objects(item, "dicom2rdf:0040A124") {
+            let value = uid_iri(value);
+            solutions.push(vec![
+                [subject.clone(), predicate.clone(), value.clone()],
+                bound(node, "rad:value", &value),
+                label.clone(),
+            ]);
+        }
+    }
+    for (value_type, value_predicate, datatypes) in TEMPORAL_VALUES {
+        if !has_value_type(value_type) {
+            continue;
+        }
+        for value in graph.objects(item, value_predicate) {
+            if value.datatype().is_some_and(|d| datatypes.contains(&d)) {
+                let value = Some(value.token().to_string());
+                solutions.push(vec![
+                    [subject.clone(), predicate.clone(), value.clone()],
+                    bound(node, "rad:value", &value),
+                    label.clone(),
+                ]);
+            }
+        }
+    }
+    if has_value_type("PNAME") {
+        for name in graph.path_objects(item, &["dicom2rdf:0040A123", "dicom2rdf:pn_alphabetic"]) {
+            let mut solution = vec![
+                [subject.clone(), predicate.clone(), node.clone()],
+                bound(node, "rdf:type", &Some("schema:Person".to_string())),
+                label.clone(),
+            ];
+            for (component_predicate, component) in PERSON_NAME {
+                for object in graph.objects(name, component) {
+                    solution.push(bound(
+                        node,
+                        component_predicate,
+                        &Some(object.token().to_string()),
+                    ));
+                }
+            }
+            solutions.push(solution);
+        }
+    }
+    // The images the coordinates of SCOORD and TCOORD items are selected from.
+    let images = || {
+        graph
+            .path_objects(item, &["dicom2rdf:0040A730", "dicom2rdf:item"])
+            .into_iter()
+            .filter(|image| {
+                graph
+                    .objects(*image, "dicom2rdf:0040A040")
+                    .iter()
+                    .any(|t| t.is_simple_literal("IMAGE"))
+            })
+            .flat_map(|image| {
+                graph.path_objects(image, &["dicom2rdf:00081199", "dicom2rdf:00081155"])
+            })
+            .collect::<Vec<_>>()
+    };
+    if ["SCOORD", "SCOORD3D"]
+        .iter()
+        .any(|value_type| has_value_type(value_type))
+    {
+        let images = images();
+        let frames_of_reference = graph.objects(item, "dicom2rdf:30060024");
+        for wkt in graph.path_objects(item, &["geo:hasGeometry", "geo:asWKT"]) {
+            let mut solution = vec![
+                [subject.clone(), predicate.clone(), node.clone()],
+                bound(node, "rdf:type", &Some("geo:Geometry".to_string())),
+                bound(node, "geo:asWKT", &Some(wkt.token().to_string())),
+                label.clone(),
+            ];
+            for (link, uids) in [
+                ("rad:image", &images),
+                ("rad:frameOfReference", &frames_of_reference),
+            ] {
+                for uid in uids {
+                    solution.push(bound(node, link, &uid_iri(*uid)));
+                }
+            }
+            solutions.push(solution);
+        }
+    }
+    if has_value_type("TCOORD") {
+        let images = images();
+        for range_type in graph.objects(item, "dicom2rdf:0040A130") {
+            let mut solution = vec![
+                [subject.clone(), predicate.clone(), node.clone()],
+                bound(
+                    node,
+                    "rad:temporalRangeType",
+                    &Some(range_type.token().to_string()),
+                ),
+                label.clone(),
+            ];
+            for (link, attribute) in [
+                ("rad:samplePosition", "dicom2rdf:0040A132"),
+                ("rad:timeOffset", "dicom2rdf:time_offset"),
+                ("rad:dateTime", "dicom2rdf:0040A13A"),
+            ] {
+                for value in graph.objects(item, attribute) {
+                    solution.push(bound(node, link, &Some(value.token().to_string())));
+                }
+            }
+            for uid in &images {
+                solution.push(bound(node, "rad:image", &uid_iri(*uid)));
+            }
+            solutions.push(solution);
+        }
+    }
+    if ["IMAGE", "COMPOSITE", "WAVEFORM"]
+        .iter()
+        .any(|value_type| has_value_type(value_type))
+    {
+        for referenced_sop in graph.objects(item, "dicom2rdf:00081199") {
+            for instance in graph.objects(referenced_sop, "dicom2rdf:00081155") {
+                let instance = uid_iri(instance);
+                let mut solution = vec![
+                    [subject.clone(), predicate.clone(), node.clone()],
+                    bound(node, "rad:siuid", &instance),
+                    label.clone(),
+                ];
+                for (reference_predicate, tag) in REFERENCED_SOP {
+                    for object in graph.objects(referenced_sop, tag) {
+                        let object = if tag == "dicom2rdf:00081150" {
+                            uid_iri(object)
+                        } else {
+                            Some(object.token().to_string())
+                        };
+                        solution.push(bound(node, reference_predicate, &object));
+                    }
+                }
+                solutions.push(solution);
+            }
+        }
+    }
+    solutions
+}
@@ -1,7 +1,3 @@
-use std::sync::atomic::{AtomicUsize, Ordering};
-
-static BLANK_NODE_COUNTER: AtomicUsize = AtomicUsize::new(0);
-
 const MAX_OBJECT_LENGTH: usize = 1000;
 
 pub struct Triple<'a> {
@@ -18,6 +14,18 @@ pub fn triple<'a>(subject: &'a IRI, predicate: &'a IRI, object: &'a TripleObject
     }
 }
 
+// Receives the triples of a document.
+pub trait TripleSink {
+    fn write_triple(&mut self, triple: Triple) -> std::io::Result<()>;
+}
+
+// Writers take one triple per line, with long literals truncated.
+impl<W: std::io::Write> TripleSink for W {
+    fn write_triple(&mut self, triple: Triple) -> std::io::Result<()> {
+        writeln!(self, "{}", triple)
+    }
+}
+
 impl std::fmt::Display for Triple<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -31,7 +39,14 @@ impl std::fmt::Display for Triple<'_> {
 #[derive(Clone)]
 pub enum IRI {
     Full(String),
-    Prefixed { prefix: String, local: String },
+    Prefixed {
+        prefix: String,
+        local: String,
+    },
+    PrefixedPath {
+        prefix: String,
+        segments: Vec<String>,
+    },
 }
 
 impl IRI {
@@ -45,12 +60,20 @@ impl IRI {
             local: local.into(),
         }
     }
+
+    pub fn prefix_path(prefix: impl Into<String>, segments: &[&str]) -> Self {
+        Self::PrefixedPath {
+            prefix: prefix.into(),
+            segments: segments.iter().map(|s| s.to_string()).collect(),
+        }
+    }
 }
 
+#[derive(Clone)]
 pub enum PlainLiteral {
     String(String),
+    LangString(String, String),
     Integer(i64),
-    Float(f64),
 }
 
 impl PlainLiteral {
@@ -59,56 +82,62 @@ impl PlainLiteral {
         f: &mut std::fmt::Formatter<'_>,
         max_len: usize,
     ) -> std::fmt::Result {
-        const ELLIPSIS: &str = "[…]";
         match &self {
-            PlainLiteral::String(s) => {
-                std::fmt::Write::write_char(f, '"')?;
-                let mut written = 0;
-                let mut start = 0;
-                for (i, c) in s.char_indices() {
-                    let escaped = match c {
-                        '\\' => Some("\\\\"),
-                        '"' => Some("\\\""),
-                        '\n' => Some("\\n"),
-                        '\r' => Some("\\r"),
-                        _ => None,
-                    };
-
-                    let pending_slice_len = i - start;
-                    let current_char_rendered_len = escaped.map_or(c.len_utf8(), |s| s.len());
-
-                    if written + pending_slice_len + current_char_rendered_len
-                        > max_len.saturating_sub(ELLIPSIS.len())
-                    {
-                        if start < i {
-                            f.write_str(&s[start..i])?;
-                        }
-                        f.write_str(ELLIPSIS)?;
-                        std::fmt::Write::write_char(f, '"')?;
-                        return Ok(());
-                    }
-
-                    if let Some(escaped_str) = escaped {
-                        if start < i {
-                            f.write_str(&s[start..i])?;
-                            written += i - start;
-                        }
-                        f.write_str(escaped_str)?;
-                        written += escaped_str.len();
-                        start = i + c.len_utf8();
-                    }
-                }
-                if start < s.len() {
-                    f.write_str(&s[start..])?;
-                }
-                std::fmt::Write::write_char(f, '"')
+            PlainLiteral::String(s) => fmt_quoted(f, s, max_len),
+            PlainLiteral::LangString(s, language) => {
+                fmt_quoted(f, s, max_len)?;
+                write!(f, "@{}", language)
             }
             PlainLiteral::Integer(n) => write!(f, "{}", n),
-            PlainLiteral::Float(x) => write!(f, "{:.1}", x),
         }
     }
 }
 
+fn fmt_quoted(f: &mut std::fmt::Formatter<'_>, s: &str, max_len: usize) -> std::fmt::Result {
+    const ELLIPSIS: &str = "[…]";
+    std::fmt::Write::write_char(f, '"')?;
+    let mut written = 0;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        let escaped = match c {
+            '\\' => Some("\\\\"),
+            '"' => Some("\\\""),
+            '\n' => Some("\\n"),
+            '\r' => Some("\\r"),
+            _ => None,
+        };
+
+        let pending_slice_len = i - start;
+        let current_char_rendered_len = escaped.map_or(c.len_utf8(), |s| s.len());
+
+        if written + pending_slice_len + current_char_rendered_len
+            > max_len.saturating_sub(ELLIPSIS.len())
+        {
+            if start < i {
+                f.write_str(&s[start..i])?;
+            }
+            f.write_str(ELLIPSIS)?;
+            std::fmt::Write::write_char(f, '"')?;
+            return Ok(());
+        }
+
+        if let Some(escaped_str) = escaped {
+            if start < i {
+                f.write_str(&s[start..i])?;
+                written += i - start;
+            }
+            f.write_str(escaped_str)?;
+            written += escaped_str.len();
+            start = i + c.len_utf8();
+        }
+    }
+    if start < s.len() {
+        f.write_str(&s[start..])?;
+    }
+    std::fmt::Write::write_char(f, '"')
+}
+
+#[derive(Clone)]
 pub struct TypedLiteral {
     lexical: String,
     datatype: IRI,
@@ -121,14 +150,24 @@ impl TypedLiteral {
             datatype,
         }
     }
+
+    pub fn lexical(&self) -> &str {
+        &self.lexical
+    }
+
+    pub fn datatype(&self) -> &IRI {
+        &self.datatype
+    }
 }
 
 impl std::fmt::Display for TypedLiteral {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "\"{}\"^^{}", self.lexical, self.datatype)
+        fmt_quoted(f, &self.lexical, usize::MAX)?;
+        write!(f, "^^{}", self.datatype)
     }
 }
 
+#[derive(Clone)]
 pub enum TripleObject {
     PlainLiteral(PlainLiteral),
     TypedLiteral(TypedLiteral),
@@ -172,15 +211,57 @@ impl std::fmt::Display for IRI {
                 let encoded_local_name = urlencoding::encode(local);
                 write!(f, "{}:{}", prefix, encoded_local_name)
             }
+            IRI::PrefixedPath { prefix, segments } => {
+                write!(f, "{}:", prefix)?;
+                for (i, segment) in segments.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "\\/")?;
+                    }
+                    write!(f, "{}", urlencoding::encode(segment))?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
-pub fn create_blank_node() -> IRI {
-    IRI::full(format!(
-        "bn{}",
-        BLANK_NODE_COUNTER
-            .fetch_add(1, Ordering::Relaxed)
-            .to_string()
-    ))
+pub fn is_absolute_iri(s: &str) -> bool {
+    let has_scheme = s.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    });
+    has_scheme
+        && s.chars()
+            .all(|c| c > ' ' && !matches!(c, '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\'))
+}
+
+// Nodes are named in the well-known genid path of the host of the raw namespace.
+pub fn skolem_iri(namespace: &str, path: &str) -> IRI {
+    let authority = namespace.find("://").map_or(0, |i| i + 3);
+    let root = namespace[authority..]
+        .find(['/', '?', '#'])
+        .map_or(namespace, |end| &namespace[..authority + end]);
+    IRI::full(format!("{}/.well-known/genid/{}", root, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skolem_iris_are_rooted_at_the_namespace_host() {
+        for namespace in [
+            "http://example.org/",
+            "http://example.org/dicom/",
+            "http://example.org#",
+            "http://example.org",
+        ] {
+            assert_eq!(
+                skolem_iri(namespace, "doc/0040A730/0/item").to_string(),
+                "<http://example.org/.well-known/genid/doc/0040A730/0/item>"
+            );
+        }
+    }
 }
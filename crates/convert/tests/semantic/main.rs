@@ -0,0 +1,941 @@
+// Compares the native semantic mapping of convert with the construct crate's CONSTRUCT queries,
+// evaluated against the raw triples of the same conversion.
+
+mod sparql;
+mod turtle;
+
+use dicom::core::value::DataSetSequence;
+use dicom::core::{DataElement, PrimitiveValue, Tag, VR, dicom_value};
+use dicom::object::{FileMetaTableBuilder, InMemDicomObject};
+use flate2::read::GzDecoder;
+use std::collections::BTreeSet;
+use std::io::Read;
+use std::path::Path;
+use std::process::Command;
+use turtle::Triple;
+
+const SOP_INSTANCE_UID: &str = "1.2.826.0.1.3680043.10.1000.1";
+const MAX_NESTING: u8 = 4;
+
+type Element = DataElement<InMemDicomObject>;
+
+fn string(tag: Tag, vr: VR, value: &str) -> Element {
+    DataElement::new(tag, vr, PrimitiveValue::from(value))
+}
+
+fn sequence(tag: Tag, items: Vec<InMemDicomObject>) -> Element {
+    DataElement::new(tag, VR::SQ, DataSetSequence::from(items))
+}
+
+fn code(tag: Tag, value: &str, scheme: &str, meaning: &str) -> Element {
+    sequence(
+        tag,
+        vec![InMemDicomObject::from_element_iter([
+            string(Tag(0x0008, 0x0100), VR::SH, value),
+            string(Tag(0x0008, 0x0102), VR::SH, scheme),
+            string(Tag(0x0008, 0x0104), VR::LO, meaning),
+        ])],
+    )
+}
+
+// A content item with a (0040,A043) Concept Name Code Sequence of DCM code `concept`.
+fn content_item(
+    relationship: &str,
+    value_type: &str,
+    concept: (&str, &str),
+    elements: Vec<Element>,
+) -> InMemDicomObject {
+    let mut item = InMemDicomObject::from_element_iter([
+        string(Tag(0x0040, 0xA010), VR::CS, relationship),
+        string(Tag(0x0040, 0xA040), VR::CS, value_type),
+        code(Tag(0x0040, 0xA043), concept.0, "DCM", concept.1),
+    ]);
+    for element in elements {
+        item.put(element);
+    }
+    item
+}
+
+fn referenced_sop(class_uid: &str, instance_uid: &str) -> Element {
+    sequence(
+        Tag(0x0008, 0x1199),
+        vec![InMemDicomObject::from_element_iter([
+            string(Tag(0x0008, 0x1150), VR::UI, class_uid),
+            string(Tag(0x0008, 0x1155), VR::UI, instance_uid),
+        ])],
+    )
+}
+
+fn content(items: Vec<InMemDicomObject>) -> Element {
+    sequence(Tag(0x0040, 0xA730), items)
+}
+
+fn template(tid: &str) -> Element {
+    sequence(
+        Tag(0x0040, 0xA504),
+        vec![InMemDicomObject::from_element_iter([
+            string(Tag(0x0008, 0x0105), VR::CS, "DCMR"),
+            string(Tag(0x0040, 0xDB00), VR::CS, tid),
+        ])],
+    )
+}
+
+fn measured_value(elements: Vec<Element>) -> Element {
+    sequence(
+        Tag(0x0040, 0xA300),
+        vec![InMemDicomObject::from_element_iter(elements)],
+    )
+}
+
+// An Enhanced SR document with the given root elements and content items.
+fn report(elements: Vec<Element>, items: Vec<InMemDicomObject>) -> InMemDicomObject {
+    let mut report = InMemDicomObject::from_element_iter([
+        string(Tag(0x0008, 0x0005), VR::CS, "ISO_IR 192"),
+        string(Tag(0x0008, 0x0016), VR::UI, "1.2.840.10008.5.1.4.1.1.88.22"),
+        string(Tag(0x0008, 0x0018), VR::UI, SOP_INSTANCE_UID),
+        string(Tag(0x0008, 0x0060), VR::CS, "SR"),
+        string(Tag(0x0010, 0x0010), VR::PN, "Doe^Jane"),
+        string(Tag(0x0020, 0x000D), VR::UI, "1.2.826.0.1.3680043.10.1000.2"),
+        string(Tag(0x0020, 0x000E), VR::UI, "1.2.826.0.1.3680043.10.1000.3"),
+        string(Tag(0x0040, 0xA040), VR::CS, "CONTAINER"),
+        code(
+            Tag(0x0040, 0xA043),
+            "126000",
+            "DCM",
+            "Imaging Measurement Report",
+        ),
+        content(items),
+    ]);
+    for element in elements {
+        report.put(element);
+    }
+    report
+}
+
+fn read_gz_files(dir: &Path, prefix: &str) -> Vec<Triple> {
+    let mut triples = Vec::new();
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        if name.starts_with(prefix) && name.ends_with(".ttl.gz") {
+            let mut text = String::new();
+            GzDecoder::new(std::fs::File::open(&path).unwrap())
+                .read_to_string(&mut text)
+                .unwrap();
+            triples.extend(turtle::parse(&text));
+        }
+    }
+    triples
+}
+
+fn assert_equivalent(dataset: InMemDicomObject) {
+    assert_equivalent_with(dataset, "");
+}
+
+// Converts `dataset` with the semantic mapping and checks that its triples are the ones the
+// construct queries build from the raw triples, which are returned. `config` is appended to the
+// repository's config.toml.
+fn assert_equivalent_with(dataset: InMemDicomObject, config: &str) -> Vec<Triple> {
+    assert_equivalent_in(dataset, config, tempfile::tempdir().unwrap().path())
+}
+
+// Like `assert_equivalent_with`, writing the output to `output_dir`.
+fn assert_equivalent_in(dataset: InMemDicomObject, config: &str, output_dir: &Path) -> Vec<Triple> {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let input_dir = tempfile::tempdir().unwrap();
+    let config_path = input_dir.path().join("config.toml");
+    std::fs::write(
+        &config_path,
+        std::fs::read_to_string(manifest_dir.join("../../config.toml")).unwrap() + config,
+    )
+    .unwrap();
+    dataset
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax("1.2.840.10008.1.2.1")
+                .media_storage_sop_class_uid("1.2.840.10008.5.1.4.1.1.88.22")
+                .media_storage_sop_instance_uid(SOP_INSTANCE_UID),
+        )
+        .unwrap()
+        .write_to_file(input_dir.path().join("report.dcm"))
+        .unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_convert"))
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--input-dir")
+        .arg(input_dir.path())
+        .arg("--output-dir")
+        .arg(output_dir)
+        .arg("--semantic")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let raw_triples = read_gz_files(output_dir, "raw-dicom-");
+    let raw = sparql::Graph::new(&raw_triples);
+    let config = config::Config::load_from_file(&config_path).unwrap();
+    let mut expected = BTreeSet::new();
+    for query in [
+        construct::top_level_construct_queries(&config),
+        construct::nested_construct_queries(&config, MAX_NESTING),
+    ]
+    .concat()
+    {
+        expected.extend(raw.construct(&sparql::parse(&query.query.to_sparql())));
+    }
+    let actual = read_gz_files(output_dir, "semantic-dicom-")
+        .into_iter()
+        .map(|(s, p, o)| (s, p, sparql::canonical(o)))
+        .collect::<BTreeSet<_>>();
+
+    let format = |triples: Vec<&Triple>| {
+        triples
+            .iter()
+            .map(|(s, p, o)| format!("  {} {} {} .", s, p, o))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let missing = expected.difference(&actual).collect::<Vec<_>>();
+    let unexpected = actual.difference(&expected).collect::<Vec<_>>();
+    assert!(
+        missing.is_empty() && unexpected.is_empty(),
+        "Only constructed by the queries:\n{}\nOnly written by the native mapping:\n{}",
+        format(missing),
+        format(unexpected)
+    );
+    assert!(!actual.is_empty());
+    raw_triples
+}
+
+#[test]
+fn sample_report() {
+    assert_equivalent(report(
+        vec![
+            string(Tag(0x0008, 0x0020), VR::DA, "20240102"),
+            string(Tag(0x0008, 0x0030), VR::TM, "101500"),
+            string(Tag(0x0008, 0x0050), VR::SH, "ACC123"),
+            string(Tag(0x0008, 0x0070), VR::LO, "Manufacturer"),
+            string(Tag(0x0008, 0x0090), VR::PN, "Referring^Doc"),
+            string(Tag(0x0010, 0x0020), VR::LO, "PID42"),
+            string(Tag(0x0010, 0x0030), VR::DA, "19700101"),
+            string(Tag(0x0010, 0x0040), VR::CS, "F"),
+            string(Tag(0x0010, 0x1020), VR::DS, "1.68"),
+            string(Tag(0x0010, 0x1030), VR::DS, "61.5"),
+            code(Tag(0x0008, 0x1032), "P5-0905E", "SRT", "CT of chest"),
+            template("1500"),
+        ],
+        vec![
+            content_item(
+                "HAS CONCEPT MOD",
+                "CODE",
+                ("121058", "Procedure reported"),
+                vec![code(Tag(0x0040, 0xA168), "P5-0905E", "SRT", "CT of chest")],
+            ),
+            content_item(
+                "HAS OBS CONTEXT",
+                "PNAME",
+                ("121008", "Person Observer Name"),
+                vec![string(Tag(0x0040, 0xA123), VR::PN, "Observer^Olga")],
+            ),
+            content_item(
+                "CONTAINS",
+                "CONTAINER",
+                ("126010", "Imaging Measurements"),
+                vec![content(vec![
+                    content_item(
+                        "CONTAINS",
+                        "NUM",
+                        ("121211", "Path length"),
+                        vec![measured_value(vec![
+                            code(Tag(0x0040, 0x08EA), "mm", "UCUM", "millimeter"),
+                            string(Tag(0x0040, 0xA30A), VR::DS, "12.50"),
+                        ])],
+                    ),
+                    content_item(
+                        "CONTAINS",
+                        "TEXT",
+                        ("121071", "Finding"),
+                        vec![string(
+                            Tag(0x0040, 0xA160),
+                            VR::UT,
+                            "Nodule \"A\" in the right\nupper lobe",
+                        )],
+                    ),
+                    content_item(
+                        "CONTAINS",
+                        "UIDREF",
+                        ("121232", "Source series for segmentation"),
+                        vec![string(
+                            Tag(0x0040, 0xA124),
+                            VR::UI,
+                            "1.2.826.0.1.3680043.10.1000.3",
+                        )],
+                    ),
+                ])],
+            ),
+        ],
+    ));
+}
+
+#[test]
+fn temporal_and_person_name_items() {
+    let output_dir = tempfile::tempdir().unwrap();
+    assert_equivalent_in(
+        report(
+            vec![],
+            vec![
+                content_item(
+                    "CONTAINS",
+                    "DATE",
+                    ("111060", "Study Date"),
+                    vec![string(Tag(0x0040, 0xA121), VR::DA, "20240102")],
+                ),
+                content_item(
+                    "CONTAINS",
+                    "DATE",
+                    ("111060", "Study Date"),
+                    vec![string(Tag(0x0040, 0xA121), VR::DA, "202401")],
+                ),
+                content_item(
+                    "CONTAINS",
+                    "TIME",
+                    ("111061", "Study Time"),
+                    vec![string(Tag(0x0040, 0xA122), VR::TM, "101530.25")],
+                ),
+                content_item(
+                    "CONTAINS",
+                    "DATETIME",
+                    ("111526", "DateTime Started"),
+                    vec![string(Tag(0x0040, 0xA120), VR::DT, "20240102101530+0100")],
+                ),
+                content_item(
+                    "CONTAINS",
+                    "DATETIME",
+                    ("111527", "DateTime Ended"),
+                    vec![string(Tag(0x0040, 0xA120), VR::DT, "2024")],
+                ),
+                content_item(
+                    "HAS OBS CONTEXT",
+                    "PNAME",
+                    ("121008", "Person Observer Name"),
+                    vec![string(
+                        Tag(0x0040, 0xA123),
+                        VR::PN,
+                        "Yamada^Tarou^^Dr.=山田^太郎=やまだ^たろう",
+                    )],
+                ),
+            ],
+        ),
+        "",
+        output_dir.path(),
+    );
+    let family_name = turtle::Term::Iri("https://schema.org/familyName".to_string());
+    assert!(
+        read_gz_files(output_dir.path(), "semantic-dicom-")
+            .iter()
+            .any(|(_, p, o)| *p == family_name && *o == turtle::Term::string("Yamada"))
+    );
+}
+
+#[test]
+fn spatial_and_temporal_coordinates() {
+    let image = || {
+        content(vec![content_item(
+            "SELECTED FROM",
+            "IMAGE",
+            ("121191", "Referenced Segment"),
+            vec![referenced_sop(
+                "1.2.840.10008.5.1.4.1.1.2",
+                "1.2.826.0.1.3680043.10.1000.4",
+            )],
+        )])
+    };
+    assert_equivalent(report(
+        vec![],
+        vec![
+            content_item(
+                "CONTAINS",
+                "SCOORD",
+                ("111030", "Image Region"),
+                vec![
+                    string(Tag(0x0070, 0x0023), VR::CS, "POLYLINE"),
+                    DataElement::new(
+                        Tag(0x0070, 0x0022),
+                        VR::FL,
+                        dicom_value!(F32, [0.0, 0.0, 10.5, 0.0, 10.5, 8.0, 0.0, 0.0]),
+                    ),
+                    image(),
+                ],
+            ),
+            content_item(
+                "CONTAINS",
+                "SCOORD",
+                ("111030", "Image Region"),
+                vec![
+                    string(Tag(0x0070, 0x0023), VR::CS, "CIRCLE"),
+                    DataElement::new(
+                        Tag(0x0070, 0x0022),
+                        VR::FL,
+                        dicom_value!(F32, [5.0, 5.0, 7.0, 5.0]),
+                    ),
+                    image(),
+                ],
+            ),
+            content_item(
+                "CONTAINS",
+                "SCOORD3D",
+                ("111030", "Image Region"),
+                vec![
+                    string(Tag(0x0070, 0x0023), VR::CS, "POINT"),
+                    DataElement::new(
+                        Tag(0x0070, 0x0022),
+                        VR::FL,
+                        dicom_value!(F32, [-12.5, 30.0, 101.25]),
+                    ),
+                    string(Tag(0x3006, 0x0024), VR::UI, "1.2.826.0.1.3680043.10.1000.5"),
+                ],
+            ),
+            content_item(
+                "CONTAINS",
+                "TCOORD",
+                ("111030", "Image Region"),
+                vec![
+                    string(Tag(0x0040, 0xA130), VR::CS, "SEGMENT"),
+                    DataElement::new(Tag(0x0040, 0xA138), VR::FD, dicom_value!(F64, [0.5, 2.25])),
+                    image(),
+                ],
+            ),
+            content_item(
+                "CONTAINS",
+                "TCOORD",
+                ("111030", "Image Region"),
+                vec![
+                    string(Tag(0x0040, 0xA130), VR::CS, "POINT"),
+                    string(Tag(0x0040, 0xA13A), VR::DT, "20240102101530.5"),
+                ],
+            ),
+        ],
+    ));
+}
+
+#[test]
+fn image_composite_and_waveform_references() {
+    let frames_and_segments = {
+        let mut image = referenced_sop(
+            "1.2.840.10008.5.1.4.1.1.66.4",
+            "1.2.826.0.1.3680043.10.1000.6",
+        );
+        if let Some(item) = image.items_mut().and_then(|items| items.first_mut()) {
+            item.put(string(Tag(0x0008, 0x1160), VR::IS, "1\\3"));
+            item.put(DataElement::new(
+                Tag(0x0062, 0x000B),
+                VR::US,
+                dicom_value!(U16, [2]),
+            ));
+        }
+        image
+    };
+    assert_equivalent(report(
+        vec![],
+        vec![
+            content_item(
+                "CONTAINS",
+                "IMAGE",
+                ("121191", "Referenced Segment"),
+                vec![frames_and_segments],
+            ),
+            content_item(
+                "CONTAINS",
+                "IMAGE",
+                ("121200", "Illustration of ROI"),
+                vec![sequence(
+                    Tag(0x0008, 0x1199),
+                    vec![InMemDicomObject::from_element_iter([string(
+                        Tag(0x0008, 0x1155),
+                        VR::UI,
+                        "1.2.826.0.1.3680043.10.1000.7",
+                    )])],
+                )],
+            ),
+            content_item(
+                "CONTAINS",
+                "COMPOSITE",
+                ("121112", "Source of Measurement"),
+                vec![referenced_sop(
+                    "1.2.840.10008.5.1.4.1.1.88.33",
+                    "1.2.826.0.1.3680043.10.1000.8",
+                )],
+            ),
+            content_item(
+                "CONTAINS",
+                "WAVEFORM",
+                ("121112", "Source of Measurement"),
+                vec![referenced_sop(
+                    "1.2.840.10008.5.1.4.1.1.9.1.1",
+                    "1.2.826.0.1.3680043.10.1000.9",
+                )],
+            ),
+        ],
+    ));
+}
+
+#[test]
+fn relationships_and_context() {
+    let text = |relationship, concept, value| {
+        content_item(
+            relationship,
+            "TEXT",
+            concept,
+            vec![string(Tag(0x0040, 0xA160), VR::UT, value)],
+        )
+    };
+    let coded = |relationship, concept, value, meaning| {
+        content_item(
+            relationship,
+            "CODE",
+            concept,
+            vec![code(Tag(0x0040, 0xA168), value, "SCT", meaning)],
+        )
+    };
+    assert_equivalent(report(
+        vec![],
+        vec![
+            text("HAS OBS CONTEXT", ("121005", "Observer Type"), "Device"),
+            coded("HAS ACQ CONTEXT", ("121139", "Modality"), "77477000", "CT"),
+            content_item(
+                "CONTAINS",
+                "CONTAINER",
+                ("121070", "Findings"),
+                vec![content(vec![
+                    coded(
+                        "HAS PROPERTIES",
+                        ("121071", "Finding"),
+                        "86049000",
+                        "Malignant neoplasm",
+                    ),
+                    coded(
+                        "HAS CONCEPT MOD",
+                        ("121401", "Derivation"),
+                        "39607008",
+                        "Lung",
+                    ),
+                    content_item(
+                        "INFERRED FROM",
+                        "NUM",
+                        ("121211", "Path length"),
+                        vec![measured_value(vec![
+                            code(Tag(0x0040, 0x08EA), "mm", "UCUM", "millimeter"),
+                            string(Tag(0x0040, 0xA30A), VR::DS, "7"),
+                        ])],
+                    ),
+                    content_item(
+                        "HAS OBS CONTEXT",
+                        "PNAME",
+                        ("121008", "Person Observer Name"),
+                        vec![string(Tag(0x0040, 0xA123), VR::PN, "Observer^Otto")],
+                    ),
+                    content_item(
+                        "CONTAINS",
+                        "CONTAINER",
+                        ("125007", "Measurement Group"),
+                        vec![content(vec![
+                            content_item(
+                                "SELECTED FROM",
+                                "IMAGE",
+                                ("121191", "Referenced Segment"),
+                                vec![referenced_sop(
+                                    "1.2.840.10008.5.1.4.1.1.2",
+                                    "1.2.826.0.1.3680043.10.1000.4",
+                                )],
+                            ),
+                            text("HAS ACQ CONTEXT", ("121106", "Comment"), "Contrast"),
+                        ])],
+                    ),
+                ])],
+            ),
+        ],
+    ));
+}
+
+#[test]
+fn templates_of_documents_and_containers() {
+    let container = |concept, elements: Vec<Element>| {
+        let mut elements = elements;
+        elements.push(content(vec![content_item(
+            "CONTAINS",
+            "TEXT",
+            ("121106", "Comment"),
+            vec![string(Tag(0x0040, 0xA160), VR::UT, "None")],
+        )]));
+        content_item("CONTAINS", "CONTAINER", concept, elements)
+    };
+    assert_equivalent(report(
+        vec![template("2000")],
+        vec![
+            container(("121070", "Findings"), vec![template("1411")]),
+            container(
+                ("113811", "CT Accumulated Dose Data"),
+                vec![template("10011")],
+            ),
+            container(
+                ("121071", "Finding"),
+                vec![sequence(
+                    Tag(0x0040, 0xA504),
+                    vec![InMemDicomObject::from_element_iter([
+                        string(Tag(0x0008, 0x0105), VR::CS, "99LOCAL"),
+                        string(Tag(0x0040, 0xDB00), VR::CS, "LOCAL 7"),
+                    ])],
+                )],
+            ),
+            container(("121072", "Impressions"), vec![]),
+        ],
+    ));
+}
+
+#[test]
+fn references_to_written_content_items() {
+    let by_reference = |relationship, positions: &[u32]| {
+        InMemDicomObject::from_element_iter([
+            string(Tag(0x0040, 0xA010), VR::CS, relationship),
+            DataElement::new(
+                Tag(0x0040, 0xDB73),
+                VR::UL,
+                PrimitiveValue::U32(positions.iter().copied().collect()),
+            ),
+        ])
+    };
+    let text = |concept, value| {
+        content_item(
+            "CONTAINS",
+            "TEXT",
+            concept,
+            vec![string(Tag(0x0040, 0xA160), VR::UT, value)],
+        )
+    };
+    let raw = assert_equivalent_with(
+        report(
+            vec![],
+            vec![
+                content_item(
+                    "CONTAINS",
+                    "CONTAINER",
+                    ("121070", "Findings"),
+                    vec![content(vec![
+                        text(("121071", "Finding"), "Nodule"),
+                        text(("121106", "Comment"), "Dropped"),
+                    ])],
+                ),
+                content_item(
+                    "CONTAINS",
+                    "NUM",
+                    ("121211", "Path length"),
+                    vec![
+                        measured_value(vec![
+                            code(Tag(0x0040, 0x08EA), "mm", "UCUM", "millimeter"),
+                            string(Tag(0x0040, 0xA30A), VR::DS, "7"),
+                        ]),
+                        content(vec![
+                            by_reference("INFERRED FROM", &[1, 1, 1]),
+                            by_reference("INFERRED FROM", &[1, 1, 2]),
+                            by_reference("HAS PROPERTIES", &[1, 1]),
+                            by_reference("INFERRED FROM", &[2, 1]),
+                        ]),
+                    ],
+                ),
+                by_reference("HAS PROPERTIES", &[1]),
+            ],
+        ),
+        "\n[[redaction_rules]]\ncode_meaning = \"Comment\"\naction = \"drop_subtree\"\n",
+    );
+    // The dropped comment and the identifier outside the document are not linked.
+    let references = raw
+        .iter()
+        .filter(|(_, p, _)| {
+            *p == turtle::Term::Iri(String::from(
+                "http://dicom2rdf.uniklinik-freiburg.de/referenced_content_item",
+            ))
+        })
+        .count();
+    assert_eq!(references, 3);
+}
+
+#[test]
+fn measurement_qualifiers_rationals_and_modifiers() {
+    let unit = || code(Tag(0x0040, 0x08EA), "mm", "UCUM", "millimeter");
+    let method = InMemDicomObject::from_element_iter([
+        string(Tag(0x0040, 0xA010), VR::CS, "HAS CONCEPT MOD"),
+        string(Tag(0x0040, 0xA040), VR::CS, "CODE"),
+        code(
+            Tag(0x0040, 0xA043),
+            "370129005",
+            "SCT",
+            "Measurement Method",
+        ),
+        code(Tag(0x0040, 0xA168), "126410", "DCM", "Shortest Axis"),
+    ]);
+    assert_equivalent(report(
+        vec![],
+        vec![
+            content_item(
+                "CONTAINS",
+                "NUM",
+                ("121211", "Path length"),
+                vec![measured_value(vec![
+                    unit(),
+                    string(Tag(0x0040, 0xA30A), VR::DS, "12.3"),
+                    DataElement::new(Tag(0x0040, 0xA161), VR::FD, dicom_value!(F64, [12.25])),
+                ])],
+            ),
+            content_item(
+                "CONTAINS",
+                "NUM",
+                ("121206", "Distance"),
+                vec![code(
+                    Tag(0x0040, 0xA301),
+                    "114006",
+                    "DCM",
+                    "Measurement failure",
+                )],
+            ),
+            content_item(
+                "CONTAINS",
+                "NUM",
+                ("121207", "Height"),
+                vec![measured_value(vec![
+                    code(Tag(0x0040, 0x08EA), "1", "UCUM", "no units"),
+                    string(Tag(0x0040, 0xA30A), VR::DS, "0.5"),
+                    DataElement::new(Tag(0x0040, 0xA162), VR::SL, dicom_value!(I32, [1])),
+                    DataElement::new(Tag(0x0040, 0xA163), VR::UL, dicom_value!(U32, [2])),
+                ])],
+            ),
+            content_item(
+                "CONTAINS",
+                "NUM",
+                ("121211", "Path length"),
+                vec![
+                    measured_value(vec![unit(), string(Tag(0x0040, 0xA30A), VR::DS, "4")]),
+                    content(vec![
+                        content_item(
+                            "HAS CONCEPT MOD",
+                            "CODE",
+                            ("121401", "Derivation"),
+                            vec![code(Tag(0x0040, 0xA168), "R-00317", "SRT", "Mean")],
+                        ),
+                        method,
+                    ]),
+                ],
+            ),
+        ],
+    ));
+}
+
+// A [pseudonymization] table with a key file in `key_dir`.
+fn pseudonymization_config(key_dir: &Path) -> String {
+    let key_file = key_dir.join("pseudonymization.key");
+    std::fs::write(&key_file, "0123456789abcdef0123456789abcdef").unwrap();
+    format!(
+        "\n[pseudonymization]\nkey_file = {:?}\n",
+        key_file.to_string_lossy()
+    )
+}
+
+// The repository's config.toml with `config` appended.
+fn load_config(dir: &Path, config: &str) -> config::Config {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let config_path = dir.join("config.toml");
+    std::fs::write(
+        &config_path,
+        std::fs::read_to_string(manifest_dir.join("../../config.toml")).unwrap() + config,
+    )
+    .unwrap();
+    config::Config::load_from_file(&config_path).unwrap()
+}
+
+fn objects(raw: &[Triple], predicate: &str) -> Vec<turtle::Term> {
+    let predicate = turtle::Term::Iri(format!(
+        "http://dicom2rdf.uniklinik-freiburg.de/{}",
+        predicate
+    ));
+    raw.iter()
+        .filter(|(_, p, _)| *p == predicate)
+        .map(|(_, _, o)| o.clone())
+        .collect()
+}
+
+#[test]
+fn pseudonyms_replace_the_values_of_profile_actions() {
+    let key_dir = tempfile::tempdir().unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+    let config = format!(
+        "\n[deidentification]\noptions = [\"clean_structured_content\"]\n{}",
+        pseudonymization_config(key_dir.path())
+    );
+    let raw = assert_equivalent_in(
+        report(vec![string(Tag(0x0010, 0x0020), VR::LO, "PID42")], vec![]),
+        &config,
+        output_dir.path(),
+    );
+    let config = load_config(key_dir.path(), &config);
+    let pseudonymization = config.pseudonymization.unwrap();
+    assert_eq!(
+        objects(&raw, "00100020"),
+        [turtle::Term::string(
+            pseudonymization
+                .pseudonym(Tag(0x0010, 0x0020), "PID42")
+                .unwrap()
+        )]
+    );
+    assert_eq!(
+        objects(&raw, "0020000D"),
+        [turtle::Term::string(
+            pseudonymization.uid("1.2.826.0.1.3680043.10.1000.2")
+        )]
+    );
+    // The actions are reported although pseudonyms replace their values.
+    let report =
+        std::fs::read_to_string(output_dir.path().join("deidentification-report.tsv")).unwrap();
+    assert!(
+        report.contains("(0010,0020)\tPatientID\tZ\t1\n"),
+        "{}",
+        report
+    );
+    assert!(
+        report.contains("(0020,000D)\tStudyInstanceUID\tU\t1\n"),
+        "{}",
+        report
+    );
+}
+
+#[test]
+fn retained_uids_are_not_pseudonymized() {
+    let key_dir = tempfile::tempdir().unwrap();
+    let raw = assert_equivalent_with(
+        report(vec![], vec![]),
+        &format!(
+            "\n[deidentification]\noptions = [\"retain_uids\", \"clean_structured_content\"]\n{}",
+            pseudonymization_config(key_dir.path())
+        ),
+    );
+    assert_eq!(
+        objects(&raw, "0020000D"),
+        [turtle::Term::string("1.2.826.0.1.3680043.10.1000.2")]
+    );
+}
+
+#[test]
+fn uid_references_are_pseudonymized() {
+    let series_uid = "1.2.826.0.1.3680043.10.1000.3";
+    let key_dir = tempfile::tempdir().unwrap();
+    let config = pseudonymization_config(key_dir.path());
+    let raw = assert_equivalent_with(
+        report(
+            vec![string(
+                Tag(0x0020, 0x0052),
+                VR::UI,
+                "1.2.826.0.1.3680043.10.1000.4",
+            )],
+            vec![content_item(
+                "CONTAINS",
+                "UIDREF",
+                ("121232", "Source series for segmentation"),
+                vec![string(Tag(0x0040, 0xA124), VR::UI, series_uid)],
+            )],
+        ),
+        &config,
+    );
+    let pseudonymization = load_config(key_dir.path(), &config)
+        .pseudonymization
+        .unwrap();
+    // The reference still points at the pseudonymized series.
+    assert_eq!(objects(&raw, "0040A124"), objects(&raw, "0020000E"));
+    assert_eq!(
+        objects(&raw, "0040A124"),
+        [turtle::Term::string(pseudonymization.uid(series_uid))]
+    );
+    assert_eq!(
+        objects(&raw, "00200052"),
+        [turtle::Term::string(
+            pseudonymization.uid("1.2.826.0.1.3680043.10.1000.4")
+        )]
+    );
+}
+
+#[test]
+fn bulk_data_digests_are_keyed_when_deidentifying() {
+    let lut = || {
+        Element::new(
+            Tag(0x0028, 0x1201),
+            VR::OW,
+            PrimitiveValue::U16(vec![0, 1, 2, 3].into()),
+        )
+    };
+    // Config, source and file meta information digests are also written.
+    let lut_digest = |raw: &[Triple]| {
+        raw.iter()
+            .filter(|(s, p, _)| {
+                matches!(s, turtle::Term::Iri(s) if s.ends_with("/00281201"))
+                    && *p
+                        == turtle::Term::Iri("http://dicom2rdf.uniklinik-freiburg.de/sha256".into())
+            })
+            .map(|(_, _, o)| o.clone())
+            .collect::<Vec<_>>()
+    };
+    let plain = assert_equivalent_with(report(vec![lut()], vec![]), "");
+    let key_dir = tempfile::tempdir().unwrap();
+    let config = format!(
+        "\n[deidentification]\n{}",
+        pseudonymization_config(key_dir.path())
+    );
+    let keyed = assert_equivalent_with(report(vec![lut()], vec![]), &config);
+    let [
+        turtle::Term::Literal {
+            lexical: digest, ..
+        },
+    ] = &lut_digest(&plain)[..]
+    else {
+        panic!("expected one digest");
+    };
+    let pseudonymization = load_config(key_dir.path(), &config)
+        .pseudonymization
+        .unwrap();
+    assert_eq!(
+        lut_digest(&keyed),
+        [turtle::Term::string(pseudonymization.hash(digest))]
+    );
+}
+
+#[test]
+fn skipped_encapsulated_documents_are_logged_when_deidentifying() {
+    let key_dir = tempfile::tempdir().unwrap();
+    let output_dir = tempfile::tempdir().unwrap();
+    assert_equivalent_in(
+        report(
+            vec![
+                Element::new(
+                    Tag(0x0042, 0x0011),
+                    VR::OB,
+                    PrimitiveValue::from(b"%PDF-1.4\n%%EOF\n".to_vec()),
+                ),
+                string(Tag(0x0042, 0x0012), VR::LO, "application/pdf"),
+            ],
+            vec![],
+        ),
+        &format!(
+            "\n[deidentification]\n{}",
+            pseudonymization_config(key_dir.path())
+        ),
+        output_dir.path(),
+    );
+    let errors = std::fs::read_to_string(output_dir.path().join("raw-dicom-000-errors.jsonl"))
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert!(
+        errors
+            .iter()
+            .any(|e| e["category"] == "encapsulated_document" && e["tag_path"] == "00420011"),
+        "{:?}",
+        errors
+    );
+}
@@ -0,0 +1,806 @@
+use crate::turtle::{RDF, Term, Triple, XSD};
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::LazyLock;
+
+// Evaluates the CONSTRUCT queries of the construct crate against the raw triples. Only the part of
+// SPARQL 1.1 those queries use is supported: basic graph patterns with blank node property lists
+// and sequence paths, OPTIONAL, BIND and FILTER, and the functions and operators in `call` and
+// `compare`. Groups are evaluated from left to right, each pattern extending the solutions so far.
+
+static XSD_DATE_TIME: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^-?[0-9]{4,}-[0-9]{2}-[0-9]{2}T[0-9]{2}:[0-9]{2}:[0-9]{2}(\.[0-9]+)?(Z|[+-][0-9]{2}:[0-9]{2})?$",
+    )
+    .unwrap()
+});
+
+static DECIMAL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[+-]?([0-9]+(\.[0-9]*)?|\.[0-9]+)$").unwrap());
+
+#[derive(Clone, Debug)]
+enum Node {
+    Variable(String),
+    Term(Term),
+}
+
+#[derive(Debug)]
+enum Pattern {
+    Triple([Node; 3]),
+    Optional(Vec<Pattern>),
+    Bind(Expression, String),
+    Filter(Expression),
+}
+
+#[derive(Debug)]
+enum Expression {
+    Variable(String),
+    Term(Term),
+    Call(String, Vec<Expression>),
+    Binary(&'static str, Box<Expression>, Box<Expression>),
+    In(Box<Expression>, Vec<Expression>),
+}
+
+pub struct Query {
+    template: Vec<[Node; 3]>,
+    pattern: Vec<Pattern>,
+}
+
+type Solution = HashMap<String, Term>;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Variable(String),
+    Iri(String),
+    Name(String),
+    String(String, Option<String>),
+    Number(String),
+    Punctuation(&'static str),
+}
+
+const PUNCTUATION: [&str; 17] = [
+    "||", "&&", "<=", ">=", "!=", "^^", "{", "}", "(", ")", "[", "]", ".", ";", ",", "/", "*",
+];
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let is_name = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | ':' | '%' | '.');
+    while i < chars.len() {
+        let c = chars[i];
+        let rest = chars[i..].iter().take(2).collect::<String>();
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '?' {
+            let end = (i + 1..chars.len())
+                .find(|&j| !(chars[j].is_alphanumeric() || chars[j] == '_'))
+                .unwrap_or(chars.len());
+            tokens.push(Token::Variable(chars[i + 1..end].iter().collect()));
+            i = end;
+        } else if c == '<'
+            && chars
+                .get(i + 1)
+                .is_some_and(|c| !c.is_whitespace() && *c != '=')
+        {
+            let end = (i..chars.len()).find(|&j| chars[j] == '>').unwrap();
+            tokens.push(Token::Iri(chars[i + 1..end].iter().collect()));
+            i = end + 1;
+        } else if c == '"' {
+            let mut lexical = String::new();
+            i += 1;
+            while chars[i] != '"' {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                lexical.push(chars[i]);
+                i += 1;
+            }
+            i += 1;
+            let mut language = None;
+            if chars.get(i) == Some(&'@') {
+                let end = (i + 1..chars.len())
+                    .find(|&j| !(chars[j].is_alphanumeric() || chars[j] == '-'))
+                    .unwrap_or(chars.len());
+                language = Some(chars[i + 1..end].iter().collect());
+                i = end;
+            }
+            tokens.push(Token::String(lexical, language));
+        } else if c.is_ascii_digit() {
+            let end = (i..chars.len())
+                .find(|&j| !(chars[j].is_ascii_digit() || chars[j] == '.'))
+                .unwrap_or(chars.len());
+            tokens.push(Token::Number(chars[i..end].iter().collect()));
+            i = end;
+        } else if let Some(punctuation) = PUNCTUATION.iter().find(|p| rest.starts_with(**p)) {
+            tokens.push(Token::Punctuation(punctuation));
+            i += punctuation.len();
+        } else if matches!(c, '=' | '<' | '>') {
+            tokens.push(Token::Punctuation(match c {
+                '=' => "=",
+                '<' => "<",
+                _ => ">",
+            }));
+            i += 1;
+        } else if is_name(c) {
+            let mut end = (i..chars.len())
+                .find(|&j| !is_name(chars[j]))
+                .unwrap_or(chars.len());
+            // A prefixed name does not end with a dot, that is the end of the triple.
+            while chars[end - 1] == '.' {
+                end -= 1;
+            }
+            tokens.push(Token::Name(chars[i..end].iter().collect()));
+            i = end;
+        } else {
+            panic!("Unexpected character {:?} in query", c);
+        }
+    }
+    tokens
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    prefixes: HashMap<String, String>,
+    blank_nodes: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.position].clone();
+        self.position += 1;
+        token
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Name(name)) if name.eq_ignore_ascii_case(keyword))
+    }
+
+    fn is_punctuation(&self, punctuation: &str) -> bool {
+        matches!(self.peek(), Some(Token::Punctuation(p)) if *p == punctuation)
+    }
+
+    fn expect(&mut self, punctuation: &str) {
+        assert!(
+            self.is_punctuation(punctuation),
+            "Expected {:?}, found {:?}",
+            punctuation,
+            self.peek()
+        );
+        self.position += 1;
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) {
+        assert!(self.is_keyword(keyword), "Expected {}", keyword);
+        self.position += 1;
+    }
+
+    fn iri(&self, name: &str) -> Term {
+        let (prefix, local) = name
+            .split_once(':')
+            .unwrap_or_else(|| panic!("Not a prefixed name: {}", name));
+        let namespace = self
+            .prefixes
+            .get(prefix)
+            .unwrap_or_else(|| panic!("Undeclared prefix: {}", prefix));
+        Term::Iri(format!("{}{}", namespace, local))
+    }
+
+    fn literal(&mut self, lexical: String, language: Option<String>) -> Term {
+        if let Some(language) = language {
+            return Term::Literal {
+                lexical,
+                datatype: format!("{}langString", RDF),
+                language: Some(language),
+            };
+        }
+        if self.is_punctuation("^^") {
+            self.position += 1;
+            let Node::Term(Term::Iri(datatype)) = self.node() else {
+                panic!("Literal datatype is not an IRI");
+            };
+            return Term::Literal {
+                lexical,
+                datatype,
+                language: None,
+            };
+        }
+        Term::string(lexical)
+    }
+
+    fn number(lexical: String) -> Term {
+        if lexical.contains('.') {
+            Term::typed(lexical, "decimal")
+        } else {
+            Term::typed(lexical, "integer")
+        }
+    }
+
+    fn query(&mut self) -> Query {
+        while self.is_keyword("PREFIX") {
+            self.position += 1;
+            let Token::Name(prefix) = self.next() else {
+                panic!("Expected a prefix name");
+            };
+            let Token::Iri(iri) = self.next() else {
+                panic!("Expected a prefix IRI");
+            };
+            self.prefixes
+                .insert(prefix.trim_end_matches(':').to_string(), iri);
+        }
+        self.expect_keyword("CONSTRUCT");
+        self.expect("{");
+        let mut template = Vec::new();
+        while !self.is_punctuation("}") {
+            self.triples(&mut template);
+        }
+        self.expect("}");
+        self.expect_keyword("WHERE");
+        let pattern = self.group();
+        assert!(self.peek().is_none(), "Unexpected {:?}", self.peek());
+        Query { template, pattern }
+    }
+
+    fn group(&mut self) -> Vec<Pattern> {
+        self.expect("{");
+        let mut patterns = Vec::new();
+        loop {
+            if self.is_punctuation("}") {
+                self.position += 1;
+                return patterns;
+            } else if self.is_punctuation(".") {
+                self.position += 1;
+            } else if self.is_keyword("OPTIONAL") {
+                self.position += 1;
+                patterns.push(Pattern::Optional(self.group()));
+            } else if self.is_keyword("BIND") {
+                self.position += 1;
+                self.expect("(");
+                let expression = self.expression();
+                self.expect_keyword("AS");
+                let Token::Variable(variable) = self.next() else {
+                    panic!("Expected a variable");
+                };
+                self.expect(")");
+                patterns.push(Pattern::Bind(expression, variable));
+            } else if self.is_keyword("FILTER") {
+                self.position += 1;
+                self.expect("(");
+                let expression = self.expression();
+                self.expect(")");
+                patterns.push(Pattern::Filter(expression));
+            } else {
+                let mut triples = Vec::new();
+                self.triples(&mut triples);
+                patterns.extend(triples.into_iter().map(Pattern::Triple));
+            }
+        }
+    }
+
+    fn node(&mut self) -> Node {
+        match self.next() {
+            Token::Variable(variable) => Node::Variable(variable),
+            Token::Iri(iri) => Node::Term(Term::Iri(iri)),
+            Token::Name(name) if name == "a" => Node::Term(Term::Iri(format!("{}type", RDF))),
+            Token::Name(name) => Node::Term(self.iri(&name)),
+            Token::String(lexical, language) => Node::Term(self.literal(lexical, language)),
+            Token::Number(lexical) => Node::Term(Self::number(lexical)),
+            token => panic!("Unexpected {:?}", token),
+        }
+    }
+
+    fn fresh_variable(&mut self) -> Node {
+        self.blank_nodes += 1;
+        Node::Variable(format!("_{}", self.blank_nodes))
+    }
+
+    // A subject with its property list, ending at a dot or the end of the group.
+    fn triples(&mut self, triples: &mut Vec<[Node; 3]>) {
+        let subject = self.node();
+        self.property_list(&subject, triples);
+        if self.is_punctuation(".") {
+            self.position += 1;
+        }
+    }
+
+    fn property_list(&mut self, subject: &Node, triples: &mut Vec<[Node; 3]>) {
+        loop {
+            let mut path = vec![self.node()];
+            while self.is_punctuation("/") {
+                self.position += 1;
+                path.push(self.node());
+            }
+            let object = if self.is_punctuation("[") {
+                self.position += 1;
+                let blank_node = self.fresh_variable();
+                let mut nested = Vec::new();
+                self.property_list(&blank_node, &mut nested);
+                self.expect("]");
+                triples.extend(nested);
+                blank_node
+            } else {
+                self.node()
+            };
+            let mut from = subject.clone();
+            let last = path.len() - 1;
+            for (i, predicate) in path.into_iter().enumerate() {
+                let to = if i == last {
+                    object.clone()
+                } else {
+                    self.fresh_variable()
+                };
+                triples.push([from, predicate, to.clone()]);
+                from = to;
+            }
+            if !self.is_punctuation(";") {
+                return;
+            }
+            self.position += 1;
+            if self.is_punctuation("]") || self.is_punctuation(".") || self.is_punctuation("}") {
+                return;
+            }
+        }
+    }
+
+    fn expression(&mut self) -> Expression {
+        let mut left = self.conjunction();
+        while self.is_punctuation("||") {
+            self.position += 1;
+            left = Expression::Binary("||", Box::new(left), Box::new(self.conjunction()));
+        }
+        left
+    }
+
+    fn conjunction(&mut self) -> Expression {
+        let mut left = self.relation();
+        while self.is_punctuation("&&") {
+            self.position += 1;
+            left = Expression::Binary("&&", Box::new(left), Box::new(self.relation()));
+        }
+        left
+    }
+
+    fn relation(&mut self) -> Expression {
+        let left = self.product();
+        for operator in ["=", "!=", "<=", ">=", "<", ">"] {
+            if self.is_punctuation(operator) {
+                self.position += 1;
+                return Expression::Binary(operator, Box::new(left), Box::new(self.product()));
+            }
+        }
+        if self.is_keyword("IN") {
+            self.position += 1;
+            return Expression::In(Box::new(left), self.arguments());
+        }
+        left
+    }
+
+    fn product(&mut self) -> Expression {
+        let mut left = self.primary();
+        while self.is_punctuation("*") {
+            self.position += 1;
+            left = Expression::Binary("*", Box::new(left), Box::new(self.primary()));
+        }
+        left
+    }
+
+    fn arguments(&mut self) -> Vec<Expression> {
+        self.expect("(");
+        let mut arguments = Vec::new();
+        while !self.is_punctuation(")") {
+            arguments.push(self.expression());
+            if self.is_punctuation(",") {
+                self.position += 1;
+            }
+        }
+        self.expect(")");
+        arguments
+    }
+
+    fn primary(&mut self) -> Expression {
+        match self.next() {
+            Token::Punctuation("(") => {
+                let expression = self.expression();
+                self.expect(")");
+                expression
+            }
+            Token::Variable(variable) => Expression::Variable(variable),
+            Token::String(lexical, language) => Expression::Term(self.literal(lexical, language)),
+            Token::Number(lexical) => Expression::Term(Self::number(lexical)),
+            Token::Iri(iri) => Expression::Term(Term::Iri(iri)),
+            Token::Name(name) if self.is_punctuation("(") => {
+                let function = if name.contains(':') {
+                    let Term::Iri(iri) = self.iri(&name) else {
+                        unreachable!()
+                    };
+                    iri
+                } else {
+                    name.to_ascii_uppercase()
+                };
+                Expression::Call(function, self.arguments())
+            }
+            Token::Name(name) => Expression::Term(self.iri(&name)),
+            token => panic!("Unexpected {:?} in expression", token),
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Query {
+    Parser {
+        tokens: tokenize(text),
+        position: 0,
+        prefixes: HashMap::new(),
+        blank_nodes: 0,
+    }
+    .query()
+}
+
+// Exact numbers as mantissa and decimal places, doubles as they are.
+#[derive(Clone, Copy, Debug)]
+enum Number {
+    Decimal(i128, u32),
+    Double(f64),
+}
+
+impl Number {
+    fn parse_decimal(lexical: &str) -> Option<Number> {
+        if !DECIMAL.is_match(lexical) {
+            return None;
+        }
+        let (integer, fraction) = lexical.split_once('.').unwrap_or((lexical, ""));
+        let digits = format!("{}{}", integer, fraction);
+        let mantissa = match digits.as_str() {
+            "" | "+" | "-" => 0,
+            digits => digits.parse().ok()?,
+        };
+        Some(Number::Decimal(mantissa, fraction.len() as u32))
+    }
+
+    fn from_term(term: &Term) -> Option<Number> {
+        let Term::Literal {
+            lexical, datatype, ..
+        } = term
+        else {
+            return None;
+        };
+        match datatype.strip_prefix(XSD)? {
+            "integer" | "decimal" => Self::parse_decimal(lexical),
+            "double" | "float" => match lexical.as_str() {
+                "INF" => Some(Number::Double(f64::INFINITY)),
+                "-INF" => Some(Number::Double(f64::NEG_INFINITY)),
+                lexical => lexical.parse().ok().map(Number::Double),
+            },
+            _ => None,
+        }
+    }
+
+    fn value(self) -> f64 {
+        match self {
+            Number::Decimal(mantissa, scale) => mantissa as f64 / 10f64.powi(scale as i32),
+            Number::Double(value) => value,
+        }
+    }
+
+    fn multiply(self, other: Number) -> Number {
+        match (self, other) {
+            (Number::Decimal(a, s), Number::Decimal(b, t)) => Number::Decimal(a * b, s + t),
+            (a, b) => Number::Double(a.value() * b.value()),
+        }
+    }
+
+    fn to_decimal(self) -> Option<Number> {
+        match self {
+            Number::Decimal(..) => Some(self),
+            Number::Double(value) if value.is_finite() => Self::parse_decimal(&value.to_string()),
+            Number::Double(_) => None,
+        }
+    }
+
+    fn to_term(self) -> Term {
+        match self {
+            Number::Decimal(mantissa, scale) => {
+                Term::typed(decimal_lexical(mantissa, scale), "decimal")
+            }
+            Number::Double(value) => Term::typed(value.to_string(), "double"),
+        }
+    }
+}
+
+// The canonical xsd:decimal form, without trailing zeros after the point.
+fn decimal_lexical(mantissa: i128, scale: u32) -> String {
+    let digits = format!(
+        "{:0>width$}",
+        mantissa.unsigned_abs(),
+        width = scale as usize + 1
+    );
+    let (integer, fraction) = digits.split_at(digits.len() - scale as usize);
+    let fraction = fraction.trim_end_matches('0');
+    let sign = if mantissa < 0 { "-" } else { "" };
+    if fraction.is_empty() {
+        format!("{}{}", sign, integer)
+    } else {
+        format!("{}{}.{}", sign, integer, fraction)
+    }
+}
+
+// Compares numeric literals by value and other terms as they are.
+pub fn canonical(term: Term) -> Term {
+    match Number::from_term(&term) {
+        Some(number @ Number::Decimal(..)) if matches!(&term, Term::Literal { datatype, .. } if datatype.ends_with("#decimal")) => {
+            number.to_term()
+        }
+        _ => term,
+    }
+}
+
+fn string_value(term: &Term) -> Option<&str> {
+    match term {
+        Term::Literal {
+            lexical, datatype, ..
+        } if datatype == &format!("{}string", XSD) || datatype == &format!("{}langString", RDF) => {
+            Some(lexical)
+        }
+        _ => None,
+    }
+}
+
+fn effective_boolean_value(term: &Term) -> Option<bool> {
+    if let Some(number) = Number::from_term(term) {
+        let value = number.value();
+        return Some(value != 0.0 && !value.is_nan());
+    }
+    match term {
+        Term::Literal {
+            lexical, datatype, ..
+        } if datatype == &format!("{}boolean", XSD) => Some(lexical == "true"),
+        term => string_value(term).map(|s| !s.is_empty()),
+    }
+}
+
+fn compare(operator: &str, left: &Term, right: &Term) -> Option<bool> {
+    if let (Some(a), Some(b)) = (Number::from_term(left), Number::from_term(right)) {
+        let (a, b) = (a.value(), b.value());
+        return Some(match operator {
+            "=" => a == b,
+            "!=" => a != b,
+            "<" => a < b,
+            ">" => a > b,
+            "<=" => a <= b,
+            _ => a >= b,
+        });
+    }
+    match operator {
+        "=" => Some(left == right),
+        "!=" => Some(left != right),
+        _ => None,
+    }
+}
+
+fn evaluate(expression: &Expression, solution: &Solution) -> Option<Term> {
+    match expression {
+        Expression::Variable(variable) => solution.get(variable).cloned(),
+        Expression::Term(term) => Some(term.clone()),
+        Expression::Binary("||", left, right) => {
+            let left = evaluate(left, solution).and_then(|t| effective_boolean_value(&t));
+            let right = evaluate(right, solution).and_then(|t| effective_boolean_value(&t));
+            match (left, right) {
+                (Some(true), _) | (_, Some(true)) => Some(Term::boolean(true)),
+                (Some(false), Some(false)) => Some(Term::boolean(false)),
+                _ => None,
+            }
+        }
+        Expression::Binary("&&", left, right) => {
+            let left = evaluate(left, solution).and_then(|t| effective_boolean_value(&t));
+            let right = evaluate(right, solution).and_then(|t| effective_boolean_value(&t));
+            match (left, right) {
+                (Some(false), _) | (_, Some(false)) => Some(Term::boolean(false)),
+                (Some(true), Some(true)) => Some(Term::boolean(true)),
+                _ => None,
+            }
+        }
+        Expression::Binary("*", left, right) => {
+            let left = Number::from_term(&evaluate(left, solution)?)?;
+            let right = Number::from_term(&evaluate(right, solution)?)?;
+            Some(left.multiply(right).to_term())
+        }
+        Expression::Binary(operator, left, right) => {
+            let left = evaluate(left, solution)?;
+            let right = evaluate(right, solution)?;
+            compare(operator, &left, &right).map(Term::boolean)
+        }
+        Expression::In(left, list) => {
+            let left = evaluate(left, solution)?;
+            let found = list
+                .iter()
+                .filter_map(|item| evaluate(item, solution))
+                .any(|item| compare("=", &left, &item) == Some(true));
+            Some(Term::boolean(found))
+        }
+        Expression::Call(function, arguments) => call(function, arguments, solution),
+    }
+}
+
+fn call(function: &str, arguments: &[Expression], solution: &Solution) -> Option<Term> {
+    match function {
+        "BOUND" => {
+            let [Expression::Variable(variable)] = arguments else {
+                panic!("BOUND needs a variable");
+            };
+            return Some(Term::boolean(solution.contains_key(variable)));
+        }
+        "IF" => {
+            let condition = effective_boolean_value(&evaluate(&arguments[0], solution)?)?;
+            return evaluate(&arguments[if condition { 1 } else { 2 }], solution);
+        }
+        "COALESCE" => {
+            return arguments
+                .iter()
+                .find_map(|argument| evaluate(argument, solution));
+        }
+        _ => {}
+    }
+    let values = arguments
+        .iter()
+        .map(|argument| evaluate(argument, solution))
+        .collect::<Option<Vec<_>>>()?;
+    match (function, values.as_slice()) {
+        ("STR", [Term::Iri(iri)]) => Some(Term::string(iri.clone())),
+        ("STR", [Term::Literal { lexical, .. }]) => Some(Term::string(lexical.clone())),
+        ("IRI", [Term::Iri(iri)]) => Some(Term::Iri(iri.clone())),
+        ("IRI", [term]) => string_value(term)
+            .filter(|s| convert::turtle::is_absolute_iri(s))
+            .map(|s| Term::Iri(s.to_string())),
+        ("CONCAT", values) => values
+            .iter()
+            .map(string_value)
+            .collect::<Option<String>>()
+            .map(Term::string),
+        ("ENCODE_FOR_URI", [term]) => {
+            string_value(term).map(|s| Term::string(urlencoding::encode(s).into_owned()))
+        }
+        ("STRAFTER", [term, separator]) => {
+            let (s, separator) = (string_value(term)?, string_value(separator)?);
+            Some(Term::string(
+                s.split_once(separator)
+                    .map_or("", |(_, after)| after)
+                    .to_string(),
+            ))
+        }
+        ("DATATYPE", [Term::Literal { datatype, .. }]) => Some(Term::Iri(datatype.clone())),
+        ("ISLITERAL", [term]) => Some(Term::boolean(matches!(term, Term::Literal { .. }))),
+        (function, [term]) if function == format!("{}decimal", XSD) => {
+            let number = match string_value(term) {
+                Some(s) => Number::parse_decimal(s),
+                None => Number::from_term(term).and_then(Number::to_decimal),
+            }?;
+            Some(number.to_term())
+        }
+        (function, [Term::Literal { lexical, .. }]) if function == format!("{}dateTime", XSD) => {
+            XSD_DATE_TIME
+                .is_match(lexical)
+                .then(|| Term::typed(lexical.clone(), "dateTime"))
+        }
+        (function, values) => panic!("Unsupported call {}({:?})", function, values),
+    }
+}
+
+fn substitute(node: &Node, solution: &Solution) -> Option<Term> {
+    match node {
+        Node::Term(term) => Some(term.clone()),
+        Node::Variable(variable) => solution.get(variable).cloned(),
+    }
+}
+
+pub struct Graph {
+    by_predicate: HashMap<Term, Vec<(Term, Term)>>,
+}
+
+impl Graph {
+    pub fn new(triples: &[Triple]) -> Self {
+        let mut by_predicate: HashMap<Term, Vec<(Term, Term)>> = HashMap::new();
+        for (subject, predicate, object) in triples {
+            by_predicate
+                .entry(predicate.clone())
+                .or_default()
+                .push((subject.clone(), object.clone()));
+        }
+        Self { by_predicate }
+    }
+
+    fn extend(
+        &self,
+        [subject, predicate, object]: &[Node; 3],
+        solution: &Solution,
+    ) -> Vec<Solution> {
+        let Some(predicate) = substitute(predicate, solution) else {
+            panic!("Variable predicates are not supported in patterns");
+        };
+        let (bound_subject, bound_object) =
+            (substitute(subject, solution), substitute(object, solution));
+        let mut solutions = Vec::new();
+        for (s, o) in self.by_predicate.get(&predicate).into_iter().flatten() {
+            if bound_subject.as_ref().is_some_and(|b| b != s)
+                || bound_object.as_ref().is_some_and(|b| b != o)
+            {
+                continue;
+            }
+            let mut extended = solution.clone();
+            for (node, term) in [(subject, s), (object, o)] {
+                if let Node::Variable(variable) = node {
+                    extended.insert(variable.clone(), term.clone());
+                }
+            }
+            solutions.push(extended);
+        }
+        solutions
+    }
+
+    fn solutions(&self, patterns: &[Pattern], mut solutions: Vec<Solution>) -> Vec<Solution> {
+        let mut filters = Vec::new();
+        for pattern in patterns {
+            solutions = match pattern {
+                Pattern::Triple(triple) => solutions
+                    .iter()
+                    .flat_map(|solution| self.extend(triple, solution))
+                    .collect(),
+                Pattern::Optional(group) => solutions
+                    .into_iter()
+                    .flat_map(|solution| {
+                        let extended = self.solutions(group, vec![solution.clone()]);
+                        if extended.is_empty() {
+                            vec![solution]
+                        } else {
+                            extended
+                        }
+                    })
+                    .collect(),
+                Pattern::Bind(expression, variable) => solutions
+                    .into_iter()
+                    .map(|mut solution| {
+                        if let Some(term) = evaluate(expression, &solution) {
+                            solution.insert(variable.clone(), term);
+                        }
+                        solution
+                    })
+                    .collect(),
+                Pattern::Filter(expression) => {
+                    filters.push(expression);
+                    solutions
+                }
+            };
+        }
+        solutions.retain(|solution| {
+            filters.iter().all(|filter| {
+                evaluate(filter, solution)
+                    .and_then(|term| effective_boolean_value(&term))
+                    .unwrap_or(false)
+            })
+        });
+        solutions
+    }
+
+    pub fn construct(&self, query: &Query) -> BTreeSet<Triple> {
+        let mut triples = BTreeSet::new();
+        for solution in self.solutions(&query.pattern, vec![Solution::new()]) {
+            for [subject, predicate, object] in &query.template {
+                if let (
+                    Some(subject @ Term::Iri(_)),
+                    Some(predicate @ Term::Iri(_)),
+                    Some(object),
+                ) = (
+                    substitute(subject, &solution),
+                    substitute(predicate, &solution),
+                    substitute(object, &solution),
+                ) {
+                    triples.insert((subject, predicate, canonical(object)));
+                }
+            }
+        }
+        triples
+    }
+}
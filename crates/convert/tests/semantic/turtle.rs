@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+pub const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+pub const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Term {
+    Iri(String),
+    Literal {
+        lexical: String,
+        datatype: String,
+        language: Option<String>,
+    },
+}
+
+impl Term {
+    pub fn string(s: impl Into<String>) -> Self {
+        Self::typed(s, "string")
+    }
+
+    pub fn typed(lexical: impl Into<String>, xsd_type: &str) -> Self {
+        Term::Literal {
+            lexical: lexical.into(),
+            datatype: format!("{}{}", XSD, xsd_type),
+            language: None,
+        }
+    }
+
+    pub fn boolean(value: bool) -> Self {
+        Self::typed(value.to_string(), "boolean")
+    }
+}
+
+impl std::fmt::Display for Term {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Term::Iri(iri) => write!(f, "<{}>", iri),
+            Term::Literal {
+                lexical,
+                language: Some(language),
+                ..
+            } => write!(f, "{:?}@{}", lexical, language),
+            Term::Literal {
+                lexical, datatype, ..
+            } => write!(f, "{:?}^^<{}>", lexical, datatype),
+        }
+    }
+}
+
+pub type Triple = (Term, Term, Term);
+
+// Reads the Turtle written by convert: prefix declarations followed by one triple per line, the
+// way a SPARQL engine loads the raw files.
+pub fn parse(text: &str) -> Vec<Triple> {
+    let mut prefixes = HashMap::new();
+    let mut triples = Vec::new();
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        if let Some(declaration) = line.strip_prefix("@prefix ") {
+            let (prefix, iri) = declaration
+                .split_once(": ")
+                .unwrap_or_else(|| panic!("Malformed prefix: {}", line));
+            let iri = iri.trim_end_matches(" .").trim_matches(['<', '>']);
+            prefixes.insert(prefix.to_string(), iri.to_string());
+            continue;
+        }
+        let mut reader = Reader {
+            chars: line.chars().collect(),
+            position: 0,
+            prefixes: &prefixes,
+        };
+        let triple = (reader.term(), reader.term(), reader.term());
+        reader.skip_whitespace();
+        assert_eq!(reader.rest(), ".", "Malformed triple: {}", line);
+        triples.push(triple);
+    }
+    triples
+}
+
+struct Reader<'a> {
+    chars: Vec<char>,
+    position: usize,
+    prefixes: &'a HashMap<String, String>,
+}
+
+impl Reader<'_> {
+    fn rest(&self) -> String {
+        self.chars[self.position..].iter().collect()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> char {
+        let c = self
+            .peek()
+            .unwrap_or_else(|| panic!("Unexpected end of line"));
+        self.position += 1;
+        c
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn term(&mut self) -> Term {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('<') => Term::Iri(self.iri()),
+            Some('"') => {
+                let lexical = self.quoted();
+                match self.peek() {
+                    Some('@') => {
+                        self.position += 1;
+                        let language = self.until_whitespace();
+                        Term::Literal {
+                            lexical,
+                            datatype: format!("{}langString", RDF),
+                            language: Some(language),
+                        }
+                    }
+                    Some('^') => {
+                        self.position += 2;
+                        let Term::Iri(datatype) = self.term() else {
+                            panic!("Literal datatype is not an IRI");
+                        };
+                        Term::Literal {
+                            lexical,
+                            datatype,
+                            language: None,
+                        }
+                    }
+                    _ => Term::string(lexical),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '-' || c == '+' => {
+                Term::typed(self.until_whitespace(), "integer")
+            }
+            _ => {
+                let name = self.until_whitespace();
+                let (prefix, local) = name
+                    .split_once(':')
+                    .unwrap_or_else(|| panic!("Not a prefixed name: {}", name));
+                let namespace = self
+                    .prefixes
+                    .get(prefix)
+                    .unwrap_or_else(|| panic!("Undeclared prefix: {}", prefix));
+                Term::Iri(format!("{}{}", namespace, local.replace('\\', "")))
+            }
+        }
+    }
+
+    fn iri(&mut self) -> String {
+        self.next();
+        let mut iri = String::new();
+        loop {
+            match self.next() {
+                '>' => return iri,
+                c => iri.push(c),
+            }
+        }
+    }
+
+    fn quoted(&mut self) -> String {
+        self.next();
+        let mut lexical = String::new();
+        loop {
+            match self.next() {
+                '"' => return lexical,
+                '\\' => match self.next() {
+                    'n' => lexical.push('\n'),
+                    'r' => lexical.push('\r'),
+                    't' => lexical.push('\t'),
+                    c => lexical.push(c),
+                },
+                c => lexical.push(c),
+            }
+        }
+    }
+
+    fn until_whitespace(&mut self) -> String {
+        let mut token = String::new();
+        while let Some(c) = self.peek().filter(|c| !c.is_whitespace()) {
+            token.push(c);
+            self.position += 1;
+        }
+        token
+    }
+}